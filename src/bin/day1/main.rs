@@ -18,6 +18,92 @@ fn count_window_increasing(data: &[i64], window_size: usize) -> usize {
     count_increasing(&window_sums(data, window_size))
 }
 
+/// Mean and (population) variance of the window sums, or `None` if there are no
+/// complete windows
+pub fn window_stats(data: &[i64], window_size: usize) -> Option<(f64, f64)> {
+    let sums = window_sums(data, window_size);
+    if sums.is_empty() {
+        return None;
+    }
+
+    let n = sums.len() as f64;
+    let mean = sums.iter().map(|&x| x as f64).sum::<f64>() / n;
+    let variance = sums.iter().map(|&x| (x as f64 - mean).powi(2)).sum::<f64>() / n;
+    Some((mean, variance))
+}
+
+pub fn count_equal(data: &[i64]) -> usize {
+    data.windows(2).filter(|pair| pair[1] == pair[0]).count()
+}
+
+/// Inclusive `(start, end)` indices of each maximal run of strictly increasing
+/// values. Runs must contain at least one increase, so single values are
+/// not included
+pub fn monotone_runs(data: &[i64]) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for i in 1..=data.len() {
+        if i == data.len() || data[i] <= data[i - 1] {
+            if i - start > 1 {
+                runs.push((start, i - 1));
+            }
+            start = i;
+        }
+    }
+    runs
+}
+
+pub fn count_strictly_decreasing(data: &[i64]) -> usize {
+    data.windows(2).filter(|pair| pair[1] < pair[0]).count()
+}
+
+/// Number of increases in the longest run of consecutive increases
+pub fn longest_increasing_streak(data: &[i64]) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    for pair in data.windows(2) {
+        if pair[1] > pair[0] {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+/// Indices of values strictly greater than both of their neighbours
+pub fn local_maxima_indices(data: &[i64]) -> Vec<usize> {
+    data.windows(3)
+        .enumerate()
+        .filter(|(_, w)| w[0] < w[1] && w[1] > w[2])
+        .map(|(i, _)| i + 1)
+        .collect()
+}
+
+/// Difference between the last and first window sums, or `None` if there are
+/// no complete windows
+pub fn range_after_window(data: &[i64], window: usize) -> Option<i64> {
+    let sums = window_sums(data, window);
+    Some(sums.last()? - sums.first()?)
+}
+
+pub fn first_index_exceeding(data: &[i64], threshold: i64) -> Option<usize> {
+    data.iter().position(|&x| x > threshold)
+}
+
+pub fn last_index_exceeding(data: &[i64], threshold: i64) -> Option<usize> {
+    data.iter().rposition(|&x| x > threshold)
+}
+
+pub fn indices_exceeding(data: &[i64], threshold: i64) -> Vec<usize> {
+    data.iter()
+        .enumerate()
+        .filter(|&(_, &x)| x > threshold)
+        .map(|(i, _)| i)
+        .collect()
+}
+
 fn main() {
     let filename = "src/bin/day1/input.txt";
     let file = File::open(filename).expect("File not found");
@@ -78,4 +164,99 @@ mod tests {
         let arr = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
         assert_eq!(count_window_increasing(&arr, 3), 5);
     }
+
+    #[test]
+    fn test_streaks() {
+        let arr = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+        assert_eq!(count_strictly_decreasing(&arr), 2);
+        assert_eq!(longest_increasing_streak(&arr), 3);
+        assert_eq!(local_maxima_indices(&arr), vec![3, 7]);
+
+        assert_eq!(count_strictly_decreasing(&[]), 0);
+        assert_eq!(longest_increasing_streak(&[]), 0);
+        assert_eq!(local_maxima_indices(&[]), vec![]);
+
+        // Monotone
+        assert_eq!(count_strictly_decreasing(&[1, 2, 3, 4]), 0);
+        assert_eq!(count_strictly_decreasing(&[4, 3, 3, 1]), 2);
+        assert_eq!(longest_increasing_streak(&[1, 2, 3, 4]), 3);
+        assert_eq!(longest_increasing_streak(&[4, 3, 2, 1]), 0);
+        assert_eq!(local_maxima_indices(&[1, 2, 3, 4]), vec![]);
+
+        // Ties break streaks and aren't maxima
+        assert_eq!(longest_increasing_streak(&[1, 2, 2, 3, 4, 5]), 3);
+        assert_eq!(local_maxima_indices(&[1, 3, 3, 1, 2, 1]), vec![4]);
+    }
+
+    #[test]
+    fn test_window_stats() {
+        let arr = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+        assert_eq!(window_stats(&arr, 3), Some((673., 4930.5)));
+        assert_eq!(window_stats(&[5, 5, 5], 1), Some((5., 0.)));
+        assert_eq!(window_stats(&arr, 11), None);
+        assert_eq!(window_stats(&[], 1), None);
+    }
+
+    #[test]
+    fn test_count_equal() {
+        let arr = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+        assert_eq!(count_equal(&arr), 0);
+        assert_eq!(count_equal(&[1, 1, 2, 2, 2]), 3);
+        assert_eq!(count_equal(&[]), 0);
+        assert_eq!(count_equal(&[1]), 0);
+
+        // Every pair is counted exactly once
+        assert_eq!(
+            count_increasing(&arr) + count_strictly_decreasing(&arr) + count_equal(&arr),
+            arr.len() - 1
+        );
+    }
+
+    #[test]
+    fn test_monotone_runs() {
+        let arr = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+        assert_eq!(monotone_runs(&arr), vec![(0, 3), (4, 7), (8, 9)]);
+        assert_eq!(monotone_runs(&[1, 2, 3, 4]), vec![(0, 3)]);
+        assert_eq!(monotone_runs(&[4, 3, 2, 1]), vec![]);
+        assert_eq!(monotone_runs(&[1, 2, 2, 3]), vec![(0, 1), (2, 3)]);
+        assert_eq!(monotone_runs(&[1]), vec![]);
+        assert_eq!(monotone_runs(&[]), vec![]);
+    }
+
+    #[test]
+    fn test_range_after_window() {
+        let arr = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+        assert_eq!(range_after_window(&arr, 1), Some(263 - 199));
+        assert_eq!(range_after_window(&arr, 3), Some(792 - 607));
+        assert_eq!(range_after_window(&arr, 10), Some(0));
+        assert_eq!(range_after_window(&arr, 11), None);
+        assert_eq!(range_after_window(&[], 1), None);
+    }
+
+    #[test]
+    fn test_indices_exceeding() {
+        let data = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+        assert_eq!(first_index_exceeding(&data, 205), Some(2));
+        assert_eq!(last_index_exceeding(&data, 205), Some(9));
+        assert_eq!(indices_exceeding(&data, 205), vec![2, 3, 5, 6, 7, 8, 9]);
+
+        // Strictly greater than
+        assert_eq!(first_index_exceeding(&data, 199), Some(1));
+        assert_eq!(last_index_exceeding(&data, 263), Some(7));
+
+        assert_eq!(first_index_exceeding(&data, i64::MIN), Some(0));
+        assert_eq!(last_index_exceeding(&data, i64::MIN), Some(9));
+        assert_eq!(
+            indices_exceeding(&data, i64::MIN),
+            (0..10).collect::<Vec<_>>()
+        );
+
+        assert_eq!(first_index_exceeding(&data, i64::MAX), None);
+        assert_eq!(last_index_exceeding(&data, i64::MAX), None);
+        assert_eq!(indices_exceeding(&data, i64::MAX), vec![]);
+
+        assert_eq!(first_index_exceeding(&[], 0), None);
+        assert_eq!(last_index_exceeding(&[], 0), None);
+        assert_eq!(indices_exceeding(&[], 0), vec![]);
+    }
 }
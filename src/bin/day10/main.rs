@@ -1,4 +1,4 @@
-use std::fs;
+use std::{fmt, fs};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let filename = "src/bin/day10/input.txt";
@@ -20,6 +20,36 @@ pub enum CheckLineError {
     UnknownChar(char),
 }
 
+impl CheckLineError {
+    /// Whether the line was corrupted by a mismatched closing character
+    pub fn is_corruption(&self) -> bool {
+        matches!(self, CheckLineError::IllegalChar(_))
+    }
+
+    /// Whether the line ended before all brackets were closed
+    pub fn is_incomplete(&self) -> bool {
+        matches!(self, CheckLineError::Incomplete(_))
+    }
+}
+
+impl fmt::Display for CheckLineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckLineError::Incomplete(remaining) => write!(
+                f,
+                "Incomplete: expecting {} closing characters",
+                remaining.len()
+            ),
+            CheckLineError::IllegalChar(symbol) => {
+                write!(f, "Unexpected closing '{}'", symbol.to_close_char())
+            }
+            CheckLineError::UnknownChar(c) => write!(f, "Unknown character '{}'", c),
+        }
+    }
+}
+
+impl std::error::Error for CheckLineError {}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Symbol {
     Bracket,
@@ -28,58 +58,120 @@ pub enum Symbol {
     Angle,
 }
 
-pub struct LineChecker {
-    stack: Vec<Symbol>,
+impl Symbol {
+    const ALL: [Symbol; 4] = [Symbol::Bracket, Symbol::Paren, Symbol::Brace, Symbol::Angle];
+
+    pub fn to_open_char(self) -> char {
+        match self {
+            Symbol::Bracket => '[',
+            Symbol::Paren => '(',
+            Symbol::Brace => '{',
+            Symbol::Angle => '<',
+        }
+    }
+
+    pub fn to_close_char(self) -> char {
+        match self {
+            Symbol::Bracket => ']',
+            Symbol::Paren => ')',
+            Symbol::Brace => '}',
+            Symbol::Angle => '>',
+        }
+    }
+
+    pub fn from_open_char(c: char) -> Option<Symbol> {
+        IntoIterator::into_iter(Self::ALL).find(|symbol| symbol.to_open_char() == c)
+    }
+
+    pub fn from_close_char(c: char) -> Option<Symbol> {
+        IntoIterator::into_iter(Self::ALL).find(|symbol| symbol.to_close_char() == c)
+    }
 }
 
-impl Default for LineChecker {
+/// The open/close character pairs recognised by a `GenericLineChecker`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BracketConfig {
+    pairs: Vec<(char, char)>,
+}
+
+impl BracketConfig {
+    pub fn new(pairs: Vec<(char, char)>) -> Self {
+        Self { pairs }
+    }
+
+    /// The four bracket pairs `[]`, `()`, `{}` and `<>`
+    pub fn standard() -> Self {
+        let pairs = Symbol::ALL
+            .iter()
+            .map(|symbol| (symbol.to_open_char(), symbol.to_close_char()))
+            .collect();
+        Self::new(pairs)
+    }
+
+    fn open_index(&self, c: char) -> Option<usize> {
+        self.pairs.iter().position(|&(open, _)| open == c)
+    }
+
+    fn close_index(&self, c: char) -> Option<usize> {
+        self.pairs.iter().position(|&(_, close)| close == c)
+    }
+}
+
+impl Default for BracketConfig {
     fn default() -> Self {
-        Self::new()
+        Self::standard()
     }
 }
 
-impl LineChecker {
-    pub fn new() -> Self {
+/// Like `CheckLineError`, but with brackets given by their characters.
+/// `Incomplete` holds the closing characters needed to complete the line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenericCheckLineError {
+    Incomplete(Vec<char>),
+    IllegalChar(char),
+    UnknownChar(char),
+}
+
+#[derive(Default)]
+pub struct GenericLineChecker {
+    config: BracketConfig,
+    /// Indices into the config's pairs of currently open brackets
+    stack: Vec<usize>,
+}
+
+impl GenericLineChecker {
+    pub fn new(config: BracketConfig) -> Self {
         Self {
+            config,
             stack: Vec::with_capacity(128),
         }
     }
 
-    pub fn check_line(&mut self, line: &str) -> Result<(), CheckLineError> {
-        use CheckLineError::*;
-        use Symbol::*;
+    pub fn check_line(&mut self, line: &str) -> Result<(), GenericCheckLineError> {
+        use GenericCheckLineError::*;
 
         // Prep for upcoming use
         self.stack.clear();
 
         for char in line.chars() {
-            match char {
-                '[' => self.stack.push(Bracket),
-                '(' => self.stack.push(Paren),
-                '{' => self.stack.push(Brace),
-                '<' => self.stack.push(Angle),
-                ']' => parse_expect(&mut self.stack, Bracket)?,
-                ')' => parse_expect(&mut self.stack, Paren)?,
-                '}' => parse_expect(&mut self.stack, Brace)?,
-                '>' => parse_expect(&mut self.stack, Angle)?,
-                c => return Err(UnknownChar(c)),
-            }
-        }
-
-        fn parse_expect(stack: &mut Vec<Symbol>, cur: Symbol) -> Result<(), CheckLineError> {
-            if let Some(actual) = stack.pop() {
-                if actual != cur {
-                    Err(IllegalChar(cur))
-                } else {
-                    Ok(())
+            if let Some(index) = self.config.open_index(char) {
+                self.stack.push(index);
+            } else if let Some(index) = self.config.close_index(char) {
+                if self.stack.pop() != Some(index) {
+                    return Err(IllegalChar(char));
                 }
             } else {
-                Err(IllegalChar(cur))
+                return Err(UnknownChar(char));
             }
         }
 
         if !self.stack.is_empty() {
-            let remaining: Vec<_> = self.stack.iter().rev().copied().collect();
+            let remaining: Vec<_> = self
+                .stack
+                .iter()
+                .rev()
+                .map(|&index| self.config.pairs[index].1)
+                .collect();
             Err(Incomplete(remaining))
         } else {
             Ok(())
@@ -87,6 +179,39 @@ impl LineChecker {
     }
 }
 
+/// Checks lines using the standard bracket pairs
+pub struct LineChecker {
+    checker: GenericLineChecker,
+}
+
+impl Default for LineChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineChecker {
+    pub fn new() -> Self {
+        Self {
+            checker: GenericLineChecker::new(BracketConfig::standard()),
+        }
+    }
+
+    pub fn check_line(&mut self, line: &str) -> Result<(), CheckLineError> {
+        fn to_symbol(close: char) -> Symbol {
+            Symbol::from_close_char(close).expect("not a standard closing bracket")
+        }
+
+        self.checker.check_line(line).map_err(|err| match err {
+            GenericCheckLineError::Incomplete(remaining) => {
+                CheckLineError::Incomplete(remaining.into_iter().map(to_symbol).collect())
+            }
+            GenericCheckLineError::IllegalChar(c) => CheckLineError::IllegalChar(to_symbol(c)),
+            GenericCheckLineError::UnknownChar(c) => CheckLineError::UnknownChar(c),
+        })
+    }
+}
+
 pub fn score_error(err: CheckLineError) -> u64 {
     use CheckLineError::*;
     use Symbol::*;
@@ -115,6 +240,14 @@ pub fn score_completion(completion: &[Symbol]) -> u64 {
         .fold(0, |acc, x| acc * 5 + x)
 }
 
+/// The closing characters which complete a line
+pub fn completion_string(completion: &[Symbol]) -> String {
+    completion
+        .iter()
+        .map(|symbol| symbol.to_close_char())
+        .collect()
+}
+
 pub fn score_illegal_lines(input: &str) -> u64 {
     let mut checker = LineChecker::new();
     input
@@ -139,6 +272,55 @@ pub fn incomplete_lines_middle_score(input: &str) -> Option<u64> {
     incomplete_scores.get(incomplete_scores.len() / 2).copied()
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileSyntaxReport {
+    pub total_lines: usize,
+    pub complete: usize,
+    pub incomplete: usize,
+    pub corrupt: usize,
+    pub unknown: usize,
+    pub illegal_score: u64,
+    pub completion_score: Option<u64>,
+}
+
+/// Checks every line of `input`, gathering all statistics in a single pass
+pub fn check_file(input: &str) -> FileSyntaxReport {
+    check_lines(input.lines())
+}
+
+/// Computes both the syntax error score and the middle completion score in a
+/// single pass over `lines`
+pub fn check_line_batch<'a>(lines: impl Iterator<Item = &'a str>) -> (u64, Option<u64>) {
+    let report = check_lines(lines);
+    (report.illegal_score, report.completion_score)
+}
+
+fn check_lines<'a>(lines: impl Iterator<Item = &'a str>) -> FileSyntaxReport {
+    let mut checker = LineChecker::new();
+    let mut report = FileSyntaxReport::default();
+    let mut incomplete_scores = Vec::new();
+
+    for line in lines {
+        report.total_lines += 1;
+        match checker.check_line(line) {
+            Ok(()) => report.complete += 1,
+            Err(CheckLineError::Incomplete(completion)) => {
+                report.incomplete += 1;
+                incomplete_scores.push(score_completion(&completion));
+            }
+            Err(err @ CheckLineError::IllegalChar(_)) => {
+                report.corrupt += 1;
+                report.illegal_score += score_error(err);
+            }
+            Err(CheckLineError::UnknownChar(_)) => report.unknown += 1,
+        }
+    }
+
+    incomplete_scores.sort_unstable();
+    report.completion_score = incomplete_scores.get(incomplete_scores.len() / 2).copied();
+    report
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -187,6 +369,44 @@ mod tests {
         assert_eq!(result, Ok(()));
     }
 
+    #[test]
+    fn test_generic_check_line() {
+        use GenericCheckLineError::*;
+
+        let config = BracketConfig::new(vec![('{', '}'), ('A', 'Z')]);
+        let mut checker = GenericLineChecker::new(config);
+        assert_eq!(checker.check_line("{AZ}"), Ok(()));
+        assert_eq!(checker.check_line("{AZ{"), Err(Incomplete(vec!['}', '}'])));
+        assert_eq!(checker.check_line("{A}Z"), Err(IllegalChar('}')));
+        assert_eq!(checker.check_line("{()}"), Err(UnknownChar('(')));
+
+        let mut checker = GenericLineChecker::default();
+        assert_eq!(checker.check_line("{([[[()]]{}]{{}})}"), Ok(()));
+        assert_eq!(checker.check_line("(<>"), Err(Incomplete(vec![')'])));
+        assert_eq!(checker.check_line("[[]]]"), Err(IllegalChar(']')));
+    }
+
+    #[test]
+    fn test_check_line_error_display() {
+        use CheckLineError::*;
+        use Symbol::*;
+
+        assert_eq!(IllegalChar(Brace).to_string(), "Unexpected closing '}'");
+        assert_eq!(IllegalChar(Angle).to_string(), "Unexpected closing '>'");
+        assert_eq!(UnknownChar('a').to_string(), "Unknown character 'a'");
+        assert_eq!(
+            Incomplete(vec![Paren, Bracket]).to_string(),
+            "Incomplete: expecting 2 closing characters"
+        );
+
+        let corrupt = IllegalChar(Paren);
+        assert!(corrupt.is_corruption() && !corrupt.is_incomplete());
+        let incomplete = Incomplete(vec![Paren]);
+        assert!(incomplete.is_incomplete() && !incomplete.is_corruption());
+        let unknown = UnknownChar('a');
+        assert!(!unknown.is_incomplete() && !unknown.is_corruption());
+    }
+
     #[test]
     fn test_score_error() {
         use CheckLineError::*;
@@ -227,8 +447,81 @@ mod tests {
         assert_eq!(score, 294);
     }
 
+    #[test]
+    fn test_completion_string() {
+        use Symbol::*;
+        assert_eq!(completion_string(&[Bracket, Paren]), "])");
+        assert_eq!(completion_string(&[]), "");
+
+        let mut checker = LineChecker::new();
+        let result = checker.check_line("[({(<(())[]>[[{[]{<()<>>");
+        match result {
+            Err(CheckLineError::Incomplete(completion)) => {
+                assert_eq!(completion_string(&completion), "}}]])})]");
+            }
+            _ => panic!("line should be incomplete"),
+        }
+    }
+
+    #[test]
+    fn test_symbol_chars() {
+        for symbol in IntoIterator::into_iter(Symbol::ALL) {
+            assert_eq!(Symbol::from_open_char(symbol.to_open_char()), Some(symbol));
+            assert_eq!(
+                Symbol::from_close_char(symbol.to_close_char()),
+                Some(symbol)
+            );
+        }
+        assert_eq!(Symbol::from_open_char(']'), None);
+        assert_eq!(Symbol::from_close_char('('), None);
+        assert_eq!(Symbol::from_close_char('a'), None);
+    }
+
     #[test]
     fn test_incomplete_lines_middle_score() {
         assert_eq!(incomplete_lines_middle_score(TEST_INPUT), Some(288957));
     }
+
+    #[test]
+    fn test_check_line_batch() {
+        let result = check_line_batch(TEST_INPUT.lines());
+        assert_eq!(result, (26397, Some(288957)));
+        assert_eq!(
+            result,
+            (
+                score_illegal_lines(TEST_INPUT),
+                incomplete_lines_middle_score(TEST_INPUT)
+            )
+        );
+
+        assert_eq!(check_line_batch("".lines()), (0, None));
+    }
+
+    #[test]
+    fn test_check_file() {
+        let report = check_file(TEST_INPUT);
+        assert_eq!(
+            report,
+            FileSyntaxReport {
+                total_lines: 10,
+                complete: 0,
+                incomplete: 5,
+                corrupt: 5,
+                unknown: 0,
+                illegal_score: 26397,
+                completion_score: Some(288957),
+            }
+        );
+
+        let report = check_file("()\n{()}\n{([[[()]]{}]{{}})}\n");
+        assert_eq!(report.total_lines, 3);
+        assert_eq!(report.complete, 3);
+        assert_eq!(report.corrupt, 0);
+        assert_eq!(report.completion_score, None);
+
+        let report = check_file("(a)\n(]\n");
+        assert_eq!(report.unknown, 1);
+        assert_eq!(report.corrupt, 1);
+        assert_eq!(report.illegal_score, 57);
+    }
 }
@@ -1,12 +1,13 @@
-use std::{collections::VecDeque, fmt::Display, fs};
+use std::{collections::VecDeque, fmt::Display, fs, str::FromStr};
 
 use itertools::Itertools;
+use rust_aoc_2021::util::grid::{parse_digit_grid, Grid};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let filename = "src/bin/day11/input.txt";
     let input = fs::read_to_string(filename).map_err(|_| "Could not read input contents")?;
 
-    let mut grid = OctopusGrid::from_str(&input).ok_or("Failed to parse input grid")?;
+    let mut grid: OctopusGrid = input.parse()?;
     let flash_total = grid.clone().simulate(100);
     println!("Flash total after 100 steps: {}", flash_total);
 
@@ -20,21 +21,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 #[derive(Clone, Copy)]
+#[repr(transparent)]
 pub struct Octopus(u8);
 
 impl Octopus {
-    /// If the octopus has any value greater than this then it is flashing
-    const MAX_INACTIVE_VALUE: u8 = 9;
+    pub fn value(&self) -> u8 {
+        self.0
+    }
 
-    pub fn is_flashing(&self) -> bool {
-        self.0 > Self::MAX_INACTIVE_VALUE
+    /// An octopus is flashing if its value is greater than `threshold`
+    pub fn is_flashing(&self, threshold: u8) -> bool {
+        self.0 > threshold
     }
 
     /// Returns `true` if the octopus started flashing as a result of the
     /// increase
-    pub fn increase(&mut self) -> bool {
-        self.0 += 1;
-        self.0 == Self::MAX_INACTIVE_VALUE + 1
+    pub fn increase(&mut self, threshold: u8) -> bool {
+        self.0 = self.0.saturating_add(1);
+        // A threshold of `u8::MAX` can never be exceeded
+        self.0 == threshold.wrapping_add(1)
     }
 
     pub fn reset(&mut self) {
@@ -42,45 +47,199 @@ impl Octopus {
     }
 }
 
-impl Display for Octopus {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.is_flashing() {
-            write!(f, "X")
-        } else {
-            write!(f, "{}", self.0)
+/// Which octopi are affected when a neighbor flashes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum NeighborhoodMode {
+    /// All eight surrounding octopi, including diagonals
+    #[default]
+    EightConnected,
+    /// Only the octopi directly left, right, above and below
+    FourConnected,
+    /// The octopi at the given offsets from the one flashing
+    Custom(Vec<(i32, i32)>),
+}
+
+impl NeighborhoodMode {
+    pub fn offsets(&self) -> &[(i32, i32)] {
+        match self {
+            NeighborhoodMode::EightConnected => &[
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (-1, 0),
+                (1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+            ],
+            NeighborhoodMode::FourConnected => &[(0, -1), (-1, 0), (1, 0), (0, 1)],
+            NeighborhoodMode::Custom(offsets) => offsets,
         }
     }
 }
 
 #[derive(Clone)]
-struct OctopusGrid {
-    width: i32,
-    height: i32,
-    grid: Vec<Octopus>,
+pub struct OctopusGrid {
+    grid: Grid<Octopus>,
+    neighborhood: NeighborhoodMode,
+    flash_threshold: u8,
+}
+
+impl FromStr for OctopusGrid {
+    type Err = &'static str;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (width, height, levels) =
+            parse_digit_grid(input).ok_or("Failed to parse input grid")?;
+        let grid = levels.into_iter().map(Octopus).collect();
+        Ok(Self {
+            grid: Grid::new(width, height, grid),
+            neighborhood: NeighborhoodMode::default(),
+            flash_threshold: Self::DEFAULT_FLASH_THRESHOLD,
+        })
+    }
 }
 
 impl OctopusGrid {
-    pub fn from_str(input: &str) -> Option<Self> {
-        let width = input.find('\n')? as i32;
-        if width == 0 {
+    /// Octopi with an energy level greater than this flash, unless set
+    /// otherwise with `with_flash_threshold`
+    pub const DEFAULT_FLASH_THRESHOLD: u8 = 9;
+
+    /// Creates a grid where the octopus at `(x, y)` has energy level `f(x, y)`
+    pub fn from_fn(width: usize, height: usize, f: impl Fn(i32, i32) -> u8) -> Self {
+        let grid = (0..height as i32)
+            .cartesian_product(0..width as i32)
+            .map(|(y, x)| Octopus(f(x, y)))
+            .collect();
+        Self {
+            grid: Grid::new(width, height, grid),
+            neighborhood: NeighborhoodMode::default(),
+            flash_threshold: Self::DEFAULT_FLASH_THRESHOLD,
+        }
+    }
+
+    /// Uses the given neighborhood when flashes spread, instead of the default
+    /// of all eight surrounding octopi
+    pub fn with_neighborhood(mut self, neighborhood: NeighborhoodMode) -> Self {
+        self.neighborhood = neighborhood;
+        self
+    }
+
+    /// Octopi flash once their energy level is greater than `threshold`
+    pub fn with_flash_threshold(mut self, threshold: u8) -> Self {
+        self.flash_threshold = threshold;
+        self
+    }
+
+    pub fn flash_threshold(&self) -> u8 {
+        self.flash_threshold
+    }
+
+    pub fn width(&self) -> usize {
+        self.grid.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.grid.height()
+    }
+
+    pub fn neighborhood(&self) -> &NeighborhoodMode {
+        &self.neighborhood
+    }
+
+    /// The energy level of the octopus at `(x, y)`, or `None` if out of bounds
+    pub fn get(&self, x: i32, y: i32) -> Option<u8> {
+        self.get_at(x, y).map(|oct| oct.0)
+    }
+
+    /// Sets the energy level of the octopus at `(x, y)`. Returns `false` if out
+    /// of bounds
+    pub fn set(&mut self, x: i32, y: i32, value: u8) -> bool {
+        match self.grid.get_mut(x, y) {
+            Some(oct) => {
+                *oct = Octopus(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Creates a new grid of the given size, copying as much of this grid as
+    /// fits (anchored at the top left) and filling the rest with `pad_value`
+    pub fn resize(&self, new_width: usize, new_height: usize, pad_value: u8) -> OctopusGrid {
+        let grid = (0..new_height as i32)
+            .cartesian_product(0..new_width as i32)
+            .map(|(y, x)| self.get_at(x, y).unwrap_or(Octopus(pad_value)))
+            .collect();
+        Self {
+            grid: Grid::new(new_width, new_height, grid),
+            neighborhood: self.neighborhood.clone(),
+            flash_threshold: self.flash_threshold,
+        }
+    }
+
+    /// Extracts the sub-grid with top left corner `(x, y)`. Returns `None` if
+    /// the sub-grid does not lie entirely within this grid
+    pub fn crop(&self, x: i32, y: i32, width: usize, height: usize) -> Option<OctopusGrid> {
+        if x < 0
+            || y < 0
+            || x as usize + width > self.grid.width()
+            || y as usize + height > self.grid.height()
+        {
             return None;
         }
 
-        let grid = input
-            .chars()
-            .filter(|c| !c.is_whitespace())
-            .map(|c| Some(Octopus(c.to_digit(10)? as u8)))
-            .collect::<Option<Vec<Octopus>>>()?;
-        let height = grid.len() as i32 / width;
+        let grid = (y..y + height as i32)
+            .cartesian_product(x..x + width as i32)
+            .map(|(y, x)| self.get_at(x, y))
+            .collect::<Option<Vec<_>>>()?;
         Some(Self {
-            width,
-            height,
-            grid,
+            grid: Grid::new(width, height, grid),
+            neighborhood: self.neighborhood.clone(),
+            flash_threshold: self.flash_threshold,
         })
     }
 
+    /// Total energy level of all octopi, counting those which are flashing as
+    /// 0 (as they will be once reset)
+    pub fn energy_sum(&self) -> u64 {
+        self.grid
+            .data()
+            .iter()
+            .filter(|oct| !oct.is_flashing(self.flash_threshold))
+            .map(|oct| oct.value() as u64)
+            .sum()
+    }
+
+    /// Number of octopi which are flashing but have not yet been reset
+    pub fn num_flashing(&self) -> usize {
+        self.grid
+            .data()
+            .iter()
+            .filter(|oct| oct.is_flashing(self.flash_threshold))
+            .count()
+    }
+
     pub fn simulate(&mut self, steps: usize) -> u64 {
-        (0..steps).map(|_| self.step()).sum()
+        self.steps().take(steps).sum()
+    }
+
+    /// Lazily steps the grid, yielding the number of flashes in each step
+    pub fn steps(&mut self) -> OctopusGridStepper<'_> {
+        OctopusGridStepper(self)
+    }
+
+    /// Steps the grid until `predicate(step_number, flash_count)` returns
+    /// `true`, returning that step number (starting from 1)
+    pub fn simulate_until(&mut self, predicate: impl Fn(u64, u64) -> bool) -> u64 {
+        let mut i = 0;
+        for flash_count in self.steps() {
+            i += 1;
+            if predicate(i, flash_count) {
+                break;
+            }
+        }
+        i
     }
 
     /// Returns the number of steps from the current grid before all octopi are
@@ -89,25 +248,29 @@ impl OctopusGrid {
     /// NOTE: if all are flashing already, this will not return 0, but rather
     /// the number of steps until the next such occurrence
     pub fn simulate_until_all_flash(&mut self) -> u64 {
-        let mut i = 0;
-        loop {
-            i += 1;
-            let flash_total = self.step();
-            if flash_total == self.grid.len() as u64 {
-                return i;
-            }
-        }
+        let octopus_count = self.grid.data().len() as u64;
+        self.simulate_until(|_, flash_count| flash_count == octopus_count)
+    }
+
+    /// Returns, for each step, the positions of the octopi which flashed
+    pub fn simulate_collecting(&mut self, steps: usize) -> Vec<Vec<(i32, i32)>> {
+        (0..steps).map(|_| self.step_with_positions().1).collect()
     }
 
     pub fn step(&mut self) -> u64 {
+        self.step_with_positions().0
+    }
+
+    /// Like `step`, but also returns the positions of the octopi which flashed
+    /// (in row-major order)
+    pub fn step_with_positions(&mut self) -> (u64, Vec<(i32, i32)>) {
         let mut unprocessed_flashing = VecDeque::new();
 
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let oct = self.entry_mut(x, y).unwrap();
-                if oct.increase() {
-                    unprocessed_flashing.push_back((x, y));
-                }
+        let threshold = self.flash_threshold;
+        let width = self.grid.width();
+        for (i, oct) in self.grid.data_mut().iter_mut().enumerate() {
+            if oct.increase(threshold) {
+                unprocessed_flashing.push_back(((i % width) as i32, (i / width) as i32));
             }
         }
 
@@ -115,31 +278,35 @@ impl OctopusGrid {
             unprocessed_flashing.extend(self.process_neighbors(x, y));
         }
 
-        self.grid
+        let flashed: Vec<_> = self
+            .grid
+            .data_mut()
             .iter_mut()
-            .filter(|oct| oct.is_flashing())
-            .fold(0, |acc, oct| {
+            .enumerate()
+            .filter(|(_, oct)| oct.is_flashing(threshold))
+            .map(|(i, oct)| {
                 oct.reset();
-                acc + 1
+                ((i % width) as i32, (i / width) as i32)
             })
+            .collect();
+        (flashed.len() as u64, flashed)
     }
 
-    fn entry_mut(&mut self, x: i32, y: i32) -> Option<&mut Octopus> {
-        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
-            return None;
-        }
-
-        let index = (y * self.width as i32 + x) as usize;
-        Some(&mut self.grid[index])
+    fn get_at(&self, x: i32, y: i32) -> Option<Octopus> {
+        self.grid.get(x, y).copied()
     }
 
-    /// Increase neighbours (all 8) and return an iterator of the neighbors that
-    /// increase began flashing
+    /// Increase neighbours (as given by the neighborhood mode) and return an
+    /// iterator of the neighbors that increase began flashing
     fn process_neighbors(&mut self, x: i32, y: i32) -> impl Iterator<Item = (i32, i32)> + '_ {
-        (x - 1..=x + 1)
-            .cartesian_product(y - 1..=y + 1)
-            .flat_map(move |(x, y)| {
-                if self.entry_mut(x, y)?.increase() {
+        let grid = &mut self.grid;
+        let threshold = self.flash_threshold;
+        self.neighborhood
+            .offsets()
+            .iter()
+            .flat_map(move |&(dx, dy)| {
+                let (x, y) = (x + dx, y + dy);
+                if grid.get_mut(x, y)?.increase(threshold) {
                     Some((x, y))
                 } else {
                     None
@@ -148,12 +315,26 @@ impl OctopusGrid {
     }
 }
 
+pub struct OctopusGridStepper<'a>(&'a mut OctopusGrid);
+
+impl<'a> Iterator for OctopusGridStepper<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.step())
+    }
+}
+
 // #[cfg(test)]
 impl Display for OctopusGrid {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                write!(f, "{}", self.grid[((y * self.width) + x) as usize])?;
+        for row in self.grid.data().chunks(self.grid.width()) {
+            for oct in row {
+                if oct.is_flashing(self.flash_threshold) {
+                    write!(f, "X")?;
+                } else {
+                    write!(f, "{}", oct.value())?;
+                }
             }
             writeln!(f)?;
         }
@@ -181,10 +362,13 @@ mod tests {
     #[test]
     fn test_parse_input() {
         let grid = OctopusGrid::from_str(TEST_INPUT).unwrap();
-        assert_eq!(grid.width, 10);
-        assert_eq!(grid.height, 10);
+        assert_eq!(grid.width(), 10);
+        assert_eq!(grid.height(), 10);
         assert_eq!(grid.grid.len(), 100);
         assert_eq!(grid.to_string(), TEST_INPUT);
+
+        // Rows of different lengths
+        assert!(OctopusGrid::from_str("12\n345\n").is_err());
     }
 
     #[test]
@@ -225,6 +409,72 @@ mod tests {
         assert_eq!(flash_count, 0);
     }
 
+    #[test]
+    fn test_flash_threshold() {
+        let mut grid = OctopusGrid::from_fn(3, 3, |_, _| 5).with_flash_threshold(5);
+        assert_eq!(grid.flash_threshold(), 5);
+        assert_eq!(grid.step(), 9);
+        assert_eq!(grid.to_string(), "000\n000\n000\n");
+
+        // With the default threshold, they all just increase
+        let mut grid = OctopusGrid::from_fn(3, 3, |_, _| 5);
+        assert_eq!(grid.flash_threshold(), 9);
+        assert_eq!(grid.step(), 0);
+        assert_eq!(grid.to_string(), "666\n666\n666\n");
+
+        // The threshold is kept when resizing
+        let grid = OctopusGrid::from_fn(3, 3, |_, _| 5)
+            .with_flash_threshold(5)
+            .resize(4, 4, 0);
+        assert_eq!(grid.flash_threshold(), 5);
+        assert_eq!(grid.crop(0, 0, 2, 2).unwrap().flash_threshold(), 5);
+
+        // Values above the threshold are shown as flashing
+        let grid = OctopusGrid::from_str("16\n61\n")
+            .unwrap()
+            .with_flash_threshold(5);
+        assert_eq!(grid.to_string(), "1X\nX1\n");
+        assert_eq!(grid.num_flashing(), 2);
+    }
+
+    #[test]
+    fn test_simulate_collecting() {
+        const EXAMPLE: &str = "\
+11111
+19991
+19191
+19991
+11111";
+
+        let mut grid = OctopusGrid::from_str(EXAMPLE).unwrap();
+        let flashes = grid.simulate_collecting(2);
+        assert_eq!(flashes.len(), 2);
+        assert_eq!(
+            flashes[0],
+            [
+                (1, 1),
+                (2, 1),
+                (3, 1),
+                (1, 2),
+                (2, 2),
+                (3, 2),
+                (1, 3),
+                (2, 3),
+                (3, 3)
+            ]
+        );
+        assert!(flashes[1].is_empty());
+
+        let mut grid = OctopusGrid::from_str(TEST_INPUT).unwrap();
+        let counts: Vec<_> = grid
+            .simulate_collecting(10)
+            .iter()
+            .map(|flashed| flashed.len() as u64)
+            .collect();
+        let mut expected = OctopusGrid::from_str(TEST_INPUT).unwrap();
+        assert_eq!(counts, expected.steps().take(10).collect::<Vec<_>>());
+    }
+
     #[test]
     fn test_simulate() {
         let mut grid = OctopusGrid::from_str(TEST_INPUT).unwrap();
@@ -246,6 +496,163 @@ mod tests {
 ";
     }
 
+    #[test]
+    fn test_steps() {
+        let mut grid = OctopusGrid::from_str(TEST_INPUT).unwrap();
+        let counts: Vec<_> = grid.steps().take(10).collect();
+        assert_eq!(counts.iter().sum::<u64>(), 204);
+        assert_eq!(counts[0], 0);
+        assert_eq!(counts[1], 35);
+
+        // Continues from where the previous steps left off
+        let total: u64 = grid.steps().take(90).sum();
+        assert_eq!(total, 1656 - 204);
+
+        let mut grid = OctopusGrid::from_str(TEST_INPUT).unwrap();
+        assert_eq!(grid.simulate_until(|_, flash_count| flash_count > 0), 2);
+        assert_eq!(grid.simulate_until(|step, _| step == 5), 5);
+    }
+
+    #[test]
+    fn test_get_set() {
+        let mut grid = OctopusGrid::from_str(TEST_INPUT).unwrap();
+        assert_eq!(grid.get(0, 0), Some(5));
+        assert_eq!(grid.get(9, 9), Some(6));
+        assert_eq!(grid.get(10, 0), None);
+        assert_eq!(grid.get(0, -1), None);
+
+        assert!(grid.set(3, 4, 7));
+        assert_eq!(grid.get(3, 4), Some(7));
+        assert!(!grid.set(10, 0, 7));
+        assert!(!grid.set(-1, 0, 7));
+    }
+
+    #[test]
+    fn test_from_fn() {
+        let grid = OctopusGrid::from_fn(3, 2, |x, y| (x + 3 * y) as u8);
+        assert_eq!(grid.to_string(), "012\n345\n");
+
+        // Nothing reaches the flashing threshold
+        let mut grid = OctopusGrid::from_fn(4, 4, |_, _| 1);
+        assert_eq!(grid.step(), 0);
+        assert_eq!(grid.to_string(), "2222\n".repeat(4));
+
+        // Everything flashes at once
+        let mut grid = OctopusGrid::from_fn(4, 4, |_, _| 9);
+        assert_eq!(grid.step(), 16);
+        assert_eq!(grid.to_string(), "0000\n".repeat(4));
+
+        let from_str = OctopusGrid::from_str(TEST_INPUT).unwrap();
+        let mut grid = OctopusGrid::from_fn(10, 10, |x, y| from_str.get(x, y).unwrap());
+        assert_eq!(grid.simulate(100), 1656);
+    }
+
+    #[test]
+    fn test_energy_sum() {
+        let mut grid = OctopusGrid::from_str(TEST_INPUT).unwrap();
+        let digit_sum: u64 = TEST_INPUT
+            .chars()
+            .filter_map(|c| c.to_digit(10))
+            .map(|d| d as u64)
+            .sum();
+        assert_eq!(grid.energy_sum(), digit_sum);
+        assert_eq!(grid.num_flashing(), 0);
+
+        grid.simulate(100);
+        assert_eq!(grid.energy_sum(), 482);
+        assert_eq!(grid.num_flashing(), 0);
+
+        assert!(grid.set(0, 0, 10));
+        assert_eq!(grid.num_flashing(), 1);
+        assert_eq!(grid.energy_sum(), 482);
+        assert_eq!(grid.grid.get(0, 0).map(Octopus::value), Some(10));
+    }
+
+    #[test]
+    fn test_neighborhood() {
+        // The corners only flash if the center flashing reaches them diagonally
+        let grid = OctopusGrid::from_str("808\n090\n808\n").unwrap();
+        assert_eq!(grid.neighborhood(), &NeighborhoodMode::EightConnected);
+
+        let mut eight = grid.clone();
+        assert_eq!(eight.step(), 5);
+        assert_eq!(eight.to_string(), "040\n404\n040\n");
+
+        let mut four = grid
+            .clone()
+            .with_neighborhood(NeighborhoodMode::FourConnected);
+        assert_eq!(four.step(), 1);
+        assert_eq!(four.to_string(), "929\n202\n929\n");
+
+        // Every octopus flashes regardless when they are all at 9
+        let all_nines = OctopusGrid::from_fn(3, 3, |_, _| 9);
+        assert_eq!(all_nines.clone().step(), 9);
+        let mut four = all_nines.with_neighborhood(NeighborhoodMode::FourConnected);
+        assert_eq!(four.step(), 9);
+
+        // Flashes only spread to the right
+        let right = NeighborhoodMode::Custom(vec![(1, 0)]);
+        let mut grid = OctopusGrid::from_str("98\n")
+            .unwrap()
+            .with_neighborhood(right);
+        assert_eq!(grid.step(), 2);
+        let left = NeighborhoodMode::Custom(vec![(-1, 0)]);
+        let mut grid = OctopusGrid::from_str("98\n")
+            .unwrap()
+            .with_neighborhood(left);
+        assert_eq!(grid.step(), 1);
+        assert_eq!(grid.to_string(), "09\n");
+
+        // Kept when resizing or cropping
+        let grid = OctopusGrid::from_str(TEST_INPUT)
+            .unwrap()
+            .with_neighborhood(NeighborhoodMode::FourConnected);
+        let resized = grid.resize(4, 4, 0);
+        assert_eq!(resized.neighborhood(), &NeighborhoodMode::FourConnected);
+        let cropped = grid.crop(1, 1, 2, 2).unwrap();
+        assert_eq!(cropped.neighborhood(), &NeighborhoodMode::FourConnected);
+    }
+
+    #[test]
+    fn test_resize_crop() {
+        let grid = OctopusGrid::from_str(TEST_INPUT).unwrap();
+
+        let small = grid.resize(5, 5, 0);
+        assert_eq!(
+            small.to_string(),
+            "\
+54831
+27458
+52645
+61413
+63573
+"
+        );
+
+        let padded = small.resize(7, 7, 0);
+        assert_eq!(
+            padded.to_string(),
+            "\
+5483100
+2745800
+5264500
+6141300
+6357300
+0000000
+0000000
+"
+        );
+
+        let cropped = padded.crop(0, 0, 5, 5).unwrap();
+        assert_eq!(cropped.to_string(), small.to_string());
+
+        let cropped = grid.crop(8, 7, 2, 3).unwrap();
+        assert_eq!(cropped.to_string(), "34\n54\n26\n");
+
+        assert!(grid.crop(8, 7, 3, 3).is_none());
+        assert!(grid.crop(-1, 0, 3, 3).is_none());
+    }
+
     #[test]
     fn test_simulate_sync() {
         let mut grid = OctopusGrid::from_str(TEST_INPUT).unwrap();
@@ -1,8 +1,11 @@
 use std::{
-    collections::{HashMap, HashSet},
-    fs,
+    cmp::Reverse,
+    collections::{BTreeSet, BinaryHeap, HashMap, HashSet, VecDeque},
+    fs, iter,
 };
 
+use itertools::Itertools;
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let filename = "src/bin/day12/input.txt";
     let input = fs::read_to_string(filename).map_err(|_| "Could not read input contents")?;
@@ -42,8 +45,7 @@ impl Cave {
     }
 }
 
-#[derive(Debug)]
-
+#[derive(Debug, Default)]
 pub struct CaveGraph {
     vertices: HashMap<String, CaveId>,
     adjacency_list: HashMap<CaveId, Vec<Cave>>,
@@ -51,44 +53,121 @@ pub struct CaveGraph {
 
 impl CaveGraph {
     pub fn parse_from_str(input: &str) -> Option<CaveGraph> {
-        let mut vertices: HashMap<&str, CaveId> = HashMap::new();
-        let mut adjacency_list: HashMap<CaveId, Vec<Cave>> = HashMap::new();
-
-        let mut id_counter = 0;
-
-        let edge = input
+        let edges = input
             .lines()
             .map(|line| line.split_once('-'))
             .collect::<Option<Vec<_>>>()?;
-        for (start, end) in edge {
-            let start_id = *vertices.entry(start).or_insert_with(|| {
-                id_counter += 1;
-                id_counter
-            });
-            let end_id = *vertices.entry(end).or_insert_with(|| {
-                id_counter += 1;
-                id_counter
-            });
-
-            adjacency_list
-                .entry(start_id)
-                .or_default()
-                .push(Cave::from(end_id, end));
-            adjacency_list
-                .entry(end_id)
-                .or_default()
-                .push(Cave::from(start_id, start));
-        }
-
-        let vertices = vertices
+
+        let mut graph = Self::default();
+        for (start, end) in edges {
+            graph.add_edge(start, end);
+        }
+        Some(graph)
+    }
+
+    /// Adds an (undirected) edge between two caves, adding the caves to the
+    /// graph if they aren't already present. Does nothing if the edge already
+    /// exists. Edges from a cave to itself are ignored, as no path can use
+    /// them (and a big cave could otherwise be revisited forever)
+    pub fn add_edge(&mut self, from: &str, to: &str) {
+        let from_id = self.add_vertex(from);
+        let to_id = self.add_vertex(to);
+        if from_id == to_id {
+            return;
+        }
+
+        let adjacent = self.adjacency_list.entry(from_id).or_default();
+        if adjacent.iter().any(|cave| cave.id == to_id) {
+            return;
+        }
+
+        adjacent.push(Cave::from(to_id, to));
+        self.adjacency_list
+            .entry(to_id)
+            .or_default()
+            .push(Cave::from(from_id, from));
+    }
+
+    fn add_vertex(&mut self, name: &str) -> CaveId {
+        let next_id = self.vertices.len() as CaveId + 1;
+        *self.vertices.entry(name.to_string()).or_insert(next_id)
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        // Each edge is stored in the adjacency lists of both its ends
+        self.adjacency_list.values().map(Vec::len).sum::<usize>() / 2
+    }
+
+    /// Whether the caves can be split into two groups such that every edge
+    /// connects caves from different groups
+    pub fn is_bipartite(&self) -> bool {
+        let mut colors: HashMap<CaveId, bool> = HashMap::new();
+        for &start in self.vertices.values() {
+            if colors.contains_key(&start) {
+                continue;
+            }
+
+            colors.insert(start, false);
+            let mut queue = VecDeque::from([start]);
+            while let Some(cave_id) = queue.pop_front() {
+                let color = colors[&cave_id];
+                for cave in self.adjacency_list.get(&cave_id).into_iter().flatten() {
+                    match colors.get(&cave.id) {
+                        Some(&other) if other == color => return false,
+                        Some(_) => {}
+                        None => {
+                            colors.insert(cave.id, !color);
+                            queue.push_back(cave.id);
+                        }
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Describes the graph in the Graphviz DOT language. Big caves are drawn
+    /// as ellipses and small caves as boxes
+    pub fn to_dot(&self) -> String {
+        let names: HashMap<CaveId, &str> = self
+            .vertices
             .iter()
-            .map(|(name, &id)| (name.to_string(), id))
+            .map(|(name, &id)| (id, name.as_str()))
             .collect();
 
-        Some(Self {
-            vertices,
-            adjacency_list,
-        })
+        let mut dot = String::from("graph {\n");
+        for name in self.vertices.keys().sorted() {
+            let shape = if Cave::is_big(name) { "ellipse" } else { "box" };
+            let fill = match name.as_str() {
+                Self::START_CAVE => ", style=filled, fillcolor=green",
+                Self::END_CAVE => ", style=filled, fillcolor=red",
+                _ => "",
+            };
+            dot += &format!("    \"{}\" [shape={}{}];\n", name, shape, fill);
+        }
+
+        let names = &names;
+        let edges: BTreeSet<_> = self
+            .adjacency_list
+            .iter()
+            .flat_map(|(id, adjacent)| {
+                let name = names[id];
+                adjacent.iter().map(move |cave| {
+                    let other = names[&cave.id];
+                    (name.min(other), name.max(other))
+                })
+            })
+            .collect();
+        for (a, b) in edges {
+            dot += &format!("    \"{}\" -- \"{}\";\n", a, b);
+        }
+
+        dot += "}\n";
+        dot
     }
 
     fn cave_id(&self, name: &str) -> Option<CaveId> {
@@ -107,6 +186,253 @@ impl CaveGraph {
     const END_CAVE: &'static str = "end";
 
     pub fn traverse(&self, allow_second_visit: bool) -> Result<u64, &'static str> {
+        let mut total_path_count = 0;
+        self.for_each_path(allow_second_visit, |_| total_path_count += 1)?;
+        Ok(total_path_count)
+    }
+
+    /// Returns every path from start to end as a list of cave names. Note that
+    /// there can be very many paths, so prefer `traverse` if only the count is
+    /// needed
+    pub fn all_paths(&self, allow_second_visit: bool) -> Result<Vec<Vec<String>>, &'static str> {
+        let names: HashMap<CaveId, &str> = self
+            .vertices
+            .iter()
+            .map(|(name, &id)| (id, name.as_str()))
+            .collect();
+
+        let mut paths = Vec::new();
+        self.for_each_path(allow_second_visit, |path| {
+            let mut path: Vec<_> = path.map(|id| names[&id].to_string()).collect();
+            path.reverse();
+            paths.push(path);
+        })?;
+        Ok(paths)
+    }
+
+    /// Returns the total number of times each cave appears across all paths
+    /// from start to end. Caves which appear in no path are omitted
+    pub fn visit_frequency(
+        &self,
+        allow_second_visit: bool,
+    ) -> Result<HashMap<CaveId, u64>, &'static str> {
+        let mut frequency = HashMap::new();
+        self.for_each_path(allow_second_visit, |path| {
+            for cave_id in path {
+                *frequency.entry(cave_id).or_insert(0) += 1;
+            }
+        })?;
+        Ok(frequency)
+    }
+
+    /// Returns the names of the `n` caves appearing most often across all
+    /// paths, along with their visit counts. Ties are broken by name
+    pub fn top_n_most_visited(
+        &self,
+        n: usize,
+        allow_second_visit: bool,
+    ) -> Result<Vec<(String, u64)>, &'static str> {
+        let frequency = self.visit_frequency(allow_second_visit)?;
+        let mut visited: Vec<_> = self
+            .vertices
+            .iter()
+            .filter_map(|(name, id)| Some((name.clone(), *frequency.get(id)?)))
+            .collect();
+        visited.sort_unstable_by(|(name1, count1), (name2, count2)| {
+            count2.cmp(count1).then_with(|| name1.cmp(name2))
+        });
+        visited.truncate(n);
+        Ok(visited)
+    }
+
+    /// Returns the fewest cave-to-cave moves needed to get from start to end,
+    /// or `None` if end can't be reached
+    pub fn shortest_path(&self) -> Result<Option<usize>, &'static str> {
+        let start = self
+            .cave_id(Self::START_CAVE)
+            .ok_or("No start cave found")?;
+        let end = self.cave_id(Self::END_CAVE).ok_or("No end cave found")?;
+
+        // A shortest path never visits a cave twice, so no special handling
+        // for big caves is needed
+        let mut visited = HashSet::from([start]);
+        let mut queue = VecDeque::from([(start, 0)]);
+        while let Some((cave_id, depth)) = queue.pop_front() {
+            if cave_id == end {
+                return Ok(Some(depth));
+            }
+
+            for cave in self.adjacency_list.get(&cave_id).into_iter().flatten() {
+                if visited.insert(cave.id) {
+                    queue.push_back((cave.id, depth + 1));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the lowest total cost of a path from start to end, where
+    /// entering a cave costs the amount given for it in `costs`. Caves missing
+    /// from `costs` cost 1 if small and 0 if big, while start and end are
+    /// always free
+    pub fn traverse_weighted(&self, costs: &HashMap<String, u32>) -> Result<u32, &'static str> {
+        let start = self
+            .cave_id(Self::START_CAVE)
+            .ok_or("No start cave found")?;
+        let end = self.cave_id(Self::END_CAVE).ok_or("No end cave found")?;
+
+        let entry_costs: HashMap<CaveId, u32> = self
+            .vertices
+            .iter()
+            .map(|(name, &id)| {
+                let cost = if id == start || id == end {
+                    0
+                } else if let Some(&cost) = costs.get(name) {
+                    cost
+                } else if Cave::is_big(name) {
+                    0
+                } else {
+                    1
+                };
+                (id, cost)
+            })
+            .collect();
+
+        // Dijkstra's algorithm. With no negative costs, the cheapest path never
+        // visits a cave twice
+        let mut best = HashMap::from([(start, 0)]);
+        let mut queue = BinaryHeap::from([Reverse((0, start))]);
+        while let Some(Reverse((cost, cave_id))) = queue.pop() {
+            if cave_id == end {
+                return Ok(cost);
+            }
+            if best[&cave_id] < cost {
+                // Already reached more cheaply
+                continue;
+            }
+
+            for cave in self.adjacency_list.get(&cave_id).into_iter().flatten() {
+                let next_cost = cost + entry_costs[&cave.id];
+                if best
+                    .get(&cave.id)
+                    .map(|&known| next_cost < known)
+                    .unwrap_or(true)
+                {
+                    best.insert(cave.id, next_cost);
+                    queue.push(Reverse((next_cost, cave.id)));
+                }
+            }
+        }
+
+        Err("No path from start to end")
+    }
+
+    pub fn has_path(&self) -> Result<bool, &'static str> {
+        Ok(self.shortest_path()?.is_some())
+    }
+
+    /// Whether every cave can be reached from every other cave
+    pub fn is_connected(&self) -> bool {
+        let first = match self.vertices.values().min() {
+            Some(&first) => first,
+            None => return true,
+        };
+
+        let mut visited = HashSet::from([first]);
+        let mut queue = VecDeque::from([first]);
+        while let Some(cave_id) = queue.pop_front() {
+            for cave in self.adjacency_list.get(&cave_id).into_iter().flatten() {
+                if visited.insert(cave.id) {
+                    queue.push_back(cave.id);
+                }
+            }
+        }
+
+        visited.len() == self.vertices.len()
+    }
+
+    /// Returns the edges whose removal would disconnect the caves at either
+    /// end, each with the lower id first, in ascending order
+    pub fn bridge_edges(&self) -> Vec<(CaveId, CaveId)> {
+        let mut bridges = self.find_cut_edges_and_vertices().0;
+        bridges.sort_unstable();
+        bridges
+    }
+
+    /// Returns the caves whose removal would disconnect some of the remaining
+    /// caves from each other
+    pub fn articulation_points(&self) -> HashSet<CaveId> {
+        self.find_cut_edges_and_vertices().1
+    }
+
+    /// Tarjan's algorithm, tracking the earliest discovered cave reachable from
+    /// each cave's subtree in a depth first search without going back along
+    /// the edge used to reach it
+    fn find_cut_edges_and_vertices(&self) -> (Vec<(CaveId, CaveId)>, HashSet<CaveId>) {
+        #[derive(Default)]
+        struct State {
+            discovered: HashMap<CaveId, usize>,
+            lowest_reachable: HashMap<CaveId, usize>,
+            bridges: Vec<(CaveId, CaveId)>,
+            articulation_points: HashSet<CaveId>,
+        }
+
+        fn visit(graph: &CaveGraph, state: &mut State, id: CaveId, parent: Option<CaveId>) {
+            let order = state.discovered.len();
+            state.discovered.insert(id, order);
+            state.lowest_reachable.insert(id, order);
+
+            let mut children = 0;
+            for cave in graph.adjacency_list.get(&id).into_iter().flatten() {
+                if Some(cave.id) == parent {
+                    continue;
+                }
+
+                let reachable = match state.discovered.get(&cave.id) {
+                    Some(&discovered) => discovered,
+                    None => {
+                        children += 1;
+                        visit(graph, state, cave.id, Some(id));
+
+                        let child_reachable = state.lowest_reachable[&cave.id];
+                        if child_reachable > order {
+                            state.bridges.push((id.min(cave.id), id.max(cave.id)));
+                        }
+                        if parent.is_some() && child_reachable >= order {
+                            state.articulation_points.insert(id);
+                        }
+                        child_reachable
+                    }
+                };
+
+                let lowest = state.lowest_reachable.get_mut(&id).unwrap();
+                *lowest = (*lowest).min(reachable);
+            }
+
+            // The root of the search only separates caves if they are in
+            // different subtrees
+            if parent.is_none() && children > 1 {
+                state.articulation_points.insert(id);
+            }
+        }
+
+        let mut state = State::default();
+        for &id in self.vertices.values().sorted() {
+            if !state.discovered.contains_key(&id) {
+                visit(self, &mut state, id, None);
+            }
+        }
+        (state.bridges, state.articulation_points)
+    }
+
+    /// Calls `on_path` for every path from start to end. Each path is given
+    /// as an iterator over its caves, in reverse order (from end to start)
+    fn for_each_path(
+        &self,
+        allow_second_visit: bool,
+        mut on_path: impl FnMut(&mut dyn Iterator<Item = CaveId>),
+    ) -> Result<(), &'static str> {
         let start = self
             .cave_id(Self::START_CAVE)
             .ok_or("No start cave found")?;
@@ -114,6 +440,8 @@ impl CaveGraph {
 
         #[derive(Clone)]
         struct PathState {
+            cave_id: CaveId,
+            parent: Option<usize>,
             allow_another_visit: bool,
             visited_small_caves: HashSet<CaveId>,
         }
@@ -122,18 +450,23 @@ impl CaveGraph {
         let mut stack = vec![(start, path_states.len())];
 
         path_states.push(PathState {
+            cave_id: start,
+            parent: None,
             allow_another_visit: allow_second_visit,
             visited_small_caves: HashSet::from([start]),
         });
 
-        let mut total_path_count = 0;
-
         while let Some((cave_id, path_idx)) = stack.pop() {
             let adjacent = self.adjacency_list.get(&cave_id);
             if let Some(adjacent) = adjacent {
                 for cave in adjacent {
                     if cave.id == end {
-                        total_path_count += 1;
+                        let states = &path_states;
+                        let mut path = iter::once(end).chain(
+                            iter::successors(Some(path_idx), |&idx| states[idx].parent)
+                                .map(|idx| states[idx].cave_id),
+                        );
+                        on_path(&mut path);
                         continue;
                     }
 
@@ -151,6 +484,8 @@ impl CaveGraph {
                     stack.push((cave.id, path_states.len()));
 
                     let mut new_state = state.clone();
+                    new_state.cave_id = cave.id;
+                    new_state.parent = Some(path_idx);
                     if !cave.big {
                         new_state.allow_another_visit = allow_another_visit;
                         new_state.visited_small_caves.insert(cave.id);
@@ -160,7 +495,7 @@ impl CaveGraph {
             }
         }
 
-        Ok(total_path_count)
+        Ok(())
     }
 }
 
@@ -249,4 +584,232 @@ start-RW";
         let result = graph.traverse(true).unwrap();
         assert_eq!(result, 3509);
     }
+
+    #[test]
+    fn test_connectivity() {
+        let graph = CaveGraph::parse_from_str(SIMPLE_INPUT).unwrap();
+        let id = |name| graph.cave_id(name).unwrap();
+        let edge = |a, b| (id(a).min(id(b)), id(a).max(id(b)));
+        assert!(graph.is_connected());
+        // c and d are dead ends
+        assert_eq!(graph.bridge_edges(), vec![edge("A", "c"), edge("b", "d")]);
+        assert_eq!(
+            graph.articulation_points(),
+            HashSet::from([id("A"), id("b")])
+        );
+
+        // Without A-end, end hangs off b alone
+        let input = SIMPLE_INPUT.replace("A-end\n", "");
+        let graph = CaveGraph::parse_from_str(&input).unwrap();
+        let id = |name| graph.cave_id(name).unwrap();
+        let edge = |a, b| (id(a).min(id(b)), id(a).max(id(b)));
+        assert!(graph.is_connected());
+        let mut expected = vec![edge("A", "c"), edge("b", "d"), edge("b", "end")];
+        expected.sort_unstable();
+        assert_eq!(graph.bridge_edges(), expected);
+
+        // Without either edge to end, it can't be reached at all
+        let input = input.replace("b-end", "end-x");
+        let graph = CaveGraph::parse_from_str(&input).unwrap();
+        assert!(!graph.is_connected());
+        assert_eq!(graph.traverse(false), Ok(0));
+        assert_eq!(graph.bridge_edges().len(), 3);
+
+        // Every edge in a cycle is safe to remove
+        let graph = CaveGraph::parse_from_str("a-b\nb-c\nc-a").unwrap();
+        assert!(graph.is_connected());
+        assert!(graph.bridge_edges().is_empty());
+        assert!(graph.articulation_points().is_empty());
+
+        assert!(CaveGraph::default().is_connected());
+    }
+
+    #[test]
+    fn test_graph_properties() {
+        let graph = CaveGraph::parse_from_str(SIMPLE_INPUT).unwrap();
+        assert_eq!(graph.vertex_count(), 6);
+        assert_eq!(graph.edge_count(), 7);
+        // start, A and b form a triangle
+        assert!(!graph.is_bipartite());
+
+        let mut graph = CaveGraph::default();
+        graph.add_edge("a", "b");
+        graph.add_edge("b", "c");
+        assert_eq!(graph.vertex_count(), 3);
+        assert_eq!(graph.edge_count(), 2);
+        assert!(graph.is_bipartite());
+
+        graph.add_edge("c", "a");
+        assert_eq!(graph.edge_count(), 3);
+        assert!(!graph.is_bipartite());
+
+        // Even cycles and separate components are fine
+        let graph = CaveGraph::parse_from_str("a-b\nb-c\nc-d\nd-a\nx-y").unwrap();
+        assert_eq!(graph.vertex_count(), 6);
+        assert_eq!(graph.edge_count(), 5);
+        assert!(graph.is_bipartite());
+
+        // Self-loops add the cave but no edge
+        let graph = CaveGraph::parse_from_str("a-a").unwrap();
+        assert_eq!(graph.vertex_count(), 1);
+        assert_eq!(graph.edge_count(), 0);
+        assert!(graph.is_bipartite());
+        let graph = CaveGraph::parse_from_str("start-A\nA-A\nA-end").unwrap();
+        assert_eq!(graph.edge_count(), 2);
+        assert_eq!(graph.traverse(false), Ok(1));
+
+        let graph = CaveGraph::default();
+        assert_eq!(graph.vertex_count(), 0);
+        assert_eq!(graph.edge_count(), 0);
+        assert!(graph.is_bipartite());
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let mut graph = CaveGraph::default();
+        graph.add_edge("start", "A");
+        graph.add_edge("A", "b");
+        graph.add_edge("b", "A");
+        graph.add_edge("b", "end");
+        assert_eq!(
+            graph.to_dot(),
+            "\
+graph {
+    \"A\" [shape=ellipse];
+    \"b\" [shape=box];
+    \"end\" [shape=box, style=filled, fillcolor=red];
+    \"start\" [shape=box, style=filled, fillcolor=green];
+    \"A\" -- \"b\";
+    \"A\" -- \"start\";
+    \"b\" -- \"end\";
+}
+"
+        );
+        assert_eq!(graph.traverse(false), Ok(1));
+
+        let dot = CaveGraph::parse_from_str(SIMPLE_INPUT).unwrap().to_dot();
+        assert!(dot.starts_with("graph {\n"));
+        assert!(dot.ends_with("}\n"));
+        for name in ["start", "A", "b", "c", "d", "end"] {
+            let node = format!("\"{}\" [", name);
+            assert_eq!(dot.matches(&node).count(), 1);
+        }
+        assert_eq!(dot.matches(" -- ").count(), 7);
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        // start-A-end
+        let graph = CaveGraph::parse_from_str(SIMPLE_INPUT).unwrap();
+        assert_eq!(graph.shortest_path(), Ok(Some(2)));
+        assert_eq!(graph.has_path(), Ok(true));
+
+        // start-dc-end
+        let graph = CaveGraph::parse_from_str(MEDIUM_INPUT).unwrap();
+        assert_eq!(graph.shortest_path(), Ok(Some(2)));
+
+        // start-DX-fs-end
+        let graph = CaveGraph::parse_from_str(LARGE_INPUT).unwrap();
+        assert_eq!(graph.shortest_path(), Ok(Some(3)));
+
+        let graph = CaveGraph::parse_from_str("start-a\nb-end").unwrap();
+        assert_eq!(graph.shortest_path(), Ok(None));
+        assert_eq!(graph.has_path(), Ok(false));
+
+        let graph = CaveGraph::parse_from_str("start-a").unwrap();
+        assert!(graph.shortest_path().is_err());
+    }
+
+    #[test]
+    fn test_traverse_weighted() {
+        let graph = CaveGraph::parse_from_str(SIMPLE_INPUT).unwrap();
+
+        // start-A-end, as A is big
+        assert_eq!(graph.traverse_weighted(&HashMap::new()), Ok(0));
+
+        // start-b-end avoids A, but costs more than the fewest moves path did
+        let costs = HashMap::from([("A".to_string(), 10)]);
+        assert_eq!(graph.traverse_weighted(&costs), Ok(1));
+
+        let costs = HashMap::from([("A".to_string(), 10), ("b".to_string(), 20)]);
+        assert_eq!(graph.traverse_weighted(&costs), Ok(10));
+
+        // Start and end are always free
+        let costs = HashMap::from([("start".to_string(), 50), ("end".to_string(), 50)]);
+        assert_eq!(graph.traverse_weighted(&costs), Ok(0));
+
+        // Two small caves can be cheaper than one expensive one
+        let graph = CaveGraph::parse_from_str("start-a\na-b\nb-end\nstart-c\nc-end").unwrap();
+        let costs = HashMap::from([("c".to_string(), 5)]);
+        assert_eq!(graph.traverse_weighted(&costs), Ok(2));
+
+        let graph = CaveGraph::parse_from_str("start-a\nb-end").unwrap();
+        assert!(graph.traverse_weighted(&HashMap::new()).is_err());
+        let graph = CaveGraph::parse_from_str("start-a").unwrap();
+        assert!(graph.traverse_weighted(&HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_all_paths() {
+        let graph = CaveGraph::parse_from_str(SIMPLE_INPUT).unwrap();
+        let mut paths = graph.all_paths(false).unwrap();
+        paths.sort();
+        assert_eq!(paths.len(), 10);
+        assert_eq!(paths[0], ["start", "A", "b", "A", "c", "A", "end"]);
+        assert_eq!(paths[9], ["start", "b", "end"]);
+
+        for input in [SIMPLE_INPUT, MEDIUM_INPUT, LARGE_INPUT] {
+            let graph = CaveGraph::parse_from_str(input).unwrap();
+            for allow_second_visit in [false, true] {
+                let paths = graph.all_paths(allow_second_visit).unwrap();
+                let count = graph.traverse(allow_second_visit).unwrap();
+                assert_eq!(paths.len() as u64, count);
+
+                for path in &paths {
+                    assert_eq!(path[0], "start");
+                    assert_eq!(path[path.len() - 1], "end");
+                }
+
+                let unique: HashSet<_> = paths.iter().collect();
+                assert_eq!(unique.len(), paths.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_visit_frequency() {
+        let graph = CaveGraph::parse_from_str(SIMPLE_INPUT).unwrap();
+        let frequency = graph.visit_frequency(false).unwrap();
+        let count = |name| {
+            let id = graph.cave_id(name).unwrap();
+            frequency.get(&id).copied().unwrap_or(0)
+        };
+
+        assert_eq!(count("start"), 10);
+        assert_eq!(count("end"), 10);
+        assert_eq!(count("A"), 17);
+        assert_eq!(count("b"), 8);
+        assert_eq!(count("c"), 5);
+        assert_eq!(count("d"), 0);
+
+        // 10 paths with a total length of 50
+        assert_eq!(frequency.values().sum::<u64>(), 50);
+    }
+
+    #[test]
+    fn test_top_n_most_visited() {
+        let graph = CaveGraph::parse_from_str(SIMPLE_INPUT).unwrap();
+        let result = graph.top_n_most_visited(3, false).unwrap();
+        assert_eq!(
+            result,
+            vec![
+                ("A".to_string(), 17),
+                ("end".to_string(), 10),
+                ("start".to_string(), 10)
+            ]
+        );
+
+        let result = graph.top_n_most_visited(10, false).unwrap();
+        assert_eq!(result.len(), 5);
+    }
 }
@@ -1,4 +1,8 @@
-use std::{collections::HashSet, fmt::Display, fs};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    fs,
+};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let filename = "src/bin/day13/input.txt";
@@ -11,21 +15,87 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     paper.apply_folds();
     println!("Points remaining after all folds: {}", paper.num_points());
     println!("After all folds:\n{}", paper);
+    match paper.ocr() {
+        Ok(code) => println!("Code: {}", code),
+        Err(err) => println!("Could not read code: {}", err),
+    }
 
     Ok(())
 }
 
+/// The letters which can be read from the paper
+const FONT_LETTERS: &str = "ABCEFGHIJKLOPRSUYZ";
+/// How each of `FONT_LETTERS` appears, including a column of spacing after each
+const FONT: [&str; FONT_HEIGHT] = [
+    ".##..###...##..####.####..##..#..#..###...##.#..#.#.....##..###..###...###.#..#.#...#####.",
+    "#..#.#..#.#..#.#....#....#..#.#..#...#.....#.#.#..#....#..#.#..#.#..#.#....#..#.#...#...#.",
+    "#..#.###..#....###..###..#....####...#.....#.##...#....#..#.#..#.#..#.#....#..#..#.#...#..",
+    "####.#..#.#....#....#....#.##.#..#...#.....#.#.#..#....#..#.###..###...##..#..#...#...#...",
+    "#..#.#..#.#..#.#....#....#..#.#..#...#..#..#.#.#..#....#..#.#....#.#.....#.#..#...#..#....",
+    "#..#.###...##..####.#.....###.#..#..###..##..#..#.####..##..#....#..#.###...##....#..####.",
+];
+const FONT_WIDTH: usize = 5;
+const FONT_HEIGHT: usize = 6;
+
+/// Maps the pixels of each letter (as a bitmask, see `Paper::ocr`) to the
+/// letter
+fn font_masks() -> HashMap<u32, char> {
+    FONT_LETTERS
+        .chars()
+        .enumerate()
+        .map(|(i, letter)| {
+            let mut mask = 0;
+            for (y, row) in FONT.iter().enumerate() {
+                for x in 0..FONT_WIDTH {
+                    if row.as_bytes()[i * FONT_WIDTH + x] == b'#' {
+                        mask |= 1 << (y * FONT_WIDTH + x);
+                    }
+                }
+            }
+            (mask, letter)
+        })
+        .collect()
+}
+
 pub struct Paper {
     points: Vec<(i32, i32)>,
+    /// Folds still to be applied, in input order
     folds: Vec<Fold>,
+    applied_folds: Vec<AppliedFold>,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Fold {
     Left(i32),
     Up(i32),
 }
 
+impl Fold {
+    /// Reflects the point across the fold line if it lies beyond it
+    fn apply(self, (x, y): (i32, i32)) -> (i32, i32) {
+        match self {
+            Fold::Left(foldx) if x > foldx => (foldx - (x - foldx), y),
+            Fold::Up(foldy) if y > foldy => (x, foldy - (y - foldy)),
+            _ => (x, y),
+        }
+    }
+
+    /// Reflects the point across the fold line
+    fn mirror(self, (x, y): (i32, i32)) -> (i32, i32) {
+        match self {
+            Fold::Left(foldx) => (foldx - (x - foldx), y),
+            Fold::Up(foldy) => (x, foldy - (y - foldy)),
+        }
+    }
+}
+
+struct AppliedFold {
+    fold: Fold,
+    /// Where the points which were moved by the fold ended up, excluding any
+    /// which landed on an existing point
+    moved: HashSet<(i32, i32)>,
+}
+
 impl Paper {
     pub fn parse_from_str(input: &str) -> Option<Paper> {
         let mut lines = input.lines();
@@ -40,7 +110,6 @@ impl Paper {
             .collect::<Option<Vec<(i32, i32)>>>()?;
 
         let folds = lines
-            .rev()
             .map(|line| {
                 let (instruction, coord) = line.split_once('=')?;
                 match instruction {
@@ -51,7 +120,11 @@ impl Paper {
             })
             .collect::<Option<Vec<Fold>>>()?;
 
-        Some(Self { points, folds })
+        Some(Self {
+            points,
+            folds,
+            applied_folds: Vec::new(),
+        })
     }
 
     pub fn apply_folds(&mut self) {
@@ -63,32 +136,31 @@ impl Paper {
     }
 
     pub fn apply_fold(&mut self) -> Option<Fold> {
-        let fold = self.folds.pop()?;
+        self.apply_fold_by_index(0)
+    }
 
-        self.points = match &fold {
-            Fold::Left(foldx) => self
-                .points
-                .iter()
-                .map(|&(x, y)| {
-                    if x > *foldx {
-                        (foldx - (x - foldx), y)
-                    } else {
-                        (x, y)
-                    }
-                })
-                .collect(),
-            Fold::Up(foldy) => self
-                .points
-                .iter()
-                .map(|&(x, y)| {
-                    if y > *foldy {
-                        (x, foldy - (y - foldy))
-                    } else {
-                        (x, y)
-                    }
-                })
-                .collect(),
-        };
+    /// Removes the fold at `index` of `pending_folds` and applies it, leaving
+    /// the others in the queue
+    pub fn apply_fold_by_index(&mut self, index: usize) -> Option<Fold> {
+        if index >= self.folds.len() {
+            return None;
+        }
+        let fold = self.folds.remove(index);
+
+        let stationary: HashSet<_> = self
+            .points
+            .iter()
+            .copied()
+            .filter(|&point| fold.apply(point) == point)
+            .collect();
+        let mut moved = HashSet::new();
+        for point in self.points.iter_mut() {
+            let folded = fold.apply(*point);
+            if folded != *point && !stationary.contains(&folded) {
+                moved.insert(folded);
+            }
+            *point = folded;
+        }
 
         // Could call this after multiple folds if performance is a concern.
         // Could even keep a tally of number of points that actually get moved
@@ -97,12 +169,141 @@ impl Paper {
         self.points.sort_unstable();
         self.points.dedup();
 
+        self.applied_folds.push(AppliedFold { fold, moved });
         Some(fold)
     }
 
+    /// Folds which have not been applied yet, in the order `apply_fold` will
+    /// apply them
+    pub fn pending_folds(&self) -> &[Fold] {
+        &self.folds
+    }
+
+    pub fn applied_fold_count(&self) -> usize {
+        self.applied_folds.len()
+    }
+
+    /// Reverses the most recently applied fold, returning it to the front of
+    /// the queue of remaining folds. Undoing works from the current points, so
+    /// points which were folded onto an existing point cannot be told apart
+    /// from it and are not restored; the result may therefore have fewer
+    /// points than before the fold
+    pub fn undo_fold(&mut self) -> Option<Fold> {
+        let AppliedFold { fold, moved } = self.applied_folds.pop()?;
+
+        for point in self.points.iter_mut() {
+            if moved.contains(point) {
+                *point = fold.mirror(*point);
+            }
+        }
+        self.points.sort_unstable();
+
+        self.folds.insert(0, fold);
+        Some(fold)
+    }
+
+    /// Applies folds until the paper fits within the given dimensions (or no
+    /// folds remain), returning the number of folds applied
+    pub fn fold_to_dimensions(&mut self, target_width: i32, target_height: i32) -> usize {
+        let mut folds_applied = 0;
+        loop {
+            let (width, height) = self.bounds();
+            if width <= target_width && height <= target_height {
+                break;
+            }
+
+            if self.apply_fold().is_none() {
+                break;
+            }
+            folds_applied += 1;
+        }
+        folds_applied
+    }
+
     pub fn num_points(&self) -> usize {
         self.points.len()
     }
+
+    /// Returns the width and height of the smallest area (with top left at the
+    /// origin) containing all points
+    pub fn bounds(&self) -> (i32, i32) {
+        if self.points.is_empty() {
+            return (0, 0);
+        }
+
+        let maxx = self.points.iter().map(|&(x, _)| x).max().unwrap_or(0);
+        let maxy = self.points.iter().map(|&(_, y)| y).max().unwrap_or(0);
+        (maxx + 1, maxy + 1)
+    }
+
+    /// Returns `(min_x, min_y, max_x, max_y)` over all points, or `None` if
+    /// there are no points
+    pub fn bounding_box(&self) -> Option<(i32, i32, i32, i32)> {
+        let min_x = self.points.iter().map(|&(x, _)| x).min()?;
+        let min_y = self.points.iter().map(|&(_, y)| y).min()?;
+        let max_x = self.points.iter().map(|&(x, _)| x).max()?;
+        let max_y = self.points.iter().map(|&(_, y)| y).max()?;
+        Some((min_x, min_y, max_x, max_y))
+    }
+
+    /// Reads the letters displayed by the points, which are expected to start
+    /// at the origin. Fails with the bitmask of the first unrecognised letter,
+    /// where bit `y * 5 + x` is set if there is a point at `(x, y)` relative to
+    /// the top left of the letter
+    pub fn ocr(&self) -> Result<String, String> {
+        let masks = font_masks();
+        let points: HashSet<_> = self.points.iter().copied().collect();
+        let (width, _) = self.bounds();
+
+        (0..width)
+            .step_by(FONT_WIDTH)
+            .map(|left| {
+                let mut mask = 0;
+                for y in 0..FONT_HEIGHT {
+                    for x in 0..FONT_WIDTH {
+                        if points.contains(&(left + x as i32, y as i32)) {
+                            mask |= 1 << (y * FONT_WIDTH + x);
+                        }
+                    }
+                }
+
+                masks
+                    .get(&mask)
+                    .copied()
+                    .ok_or_else(|| format!("Unrecognised letter with mask {:#x}", mask))
+            })
+            .collect()
+    }
+
+    /// Renders the points as an SVG image, with each point a square of side
+    /// `cell_size`
+    pub fn to_svg(&self, cell_size: u32) -> String {
+        let cell_size = cell_size as i32;
+        let (min_x, min_y, max_x, max_y) = self.bounding_box().unwrap_or((0, 0, -1, -1));
+        let width = (max_x - min_x + 1) * cell_size;
+        let height = (max_y - min_y + 1) * cell_size;
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}\" height=\"{}\">\n",
+            min_x * cell_size,
+            min_y * cell_size,
+            width,
+            height,
+            width,
+            height
+        );
+        for &(x, y) in &self.points {
+            svg += &format!(
+                "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+                x * cell_size,
+                y * cell_size,
+                cell_size,
+                cell_size
+            );
+        }
+        svg += "</svg>\n";
+        svg
+    }
 }
 
 impl Display for Paper {
@@ -163,8 +364,7 @@ fold along x=5
         assert_eq!(paper.points[1], (0, 14));
         assert_eq!(paper.points[17], (9, 0));
 
-        // Reversed (as a stack)
-        assert_eq!(paper.folds, vec![Fold::Left(5), Fold::Up(7)]);
+        assert_eq!(paper.pending_folds(), [Fold::Up(7), Fold::Left(5)]);
     }
 
     #[test]
@@ -183,6 +383,43 @@ fold along x=5
         assert_eq!(paper.num_points(), 16);
     }
 
+    #[test]
+    fn test_apply_fold_by_index() {
+        let mut forward = Paper::parse_from_str(EXAMPLE_INPUT).unwrap();
+        assert_eq!(forward.apply_fold_by_index(2), None);
+        assert_eq!(forward.apply_fold_by_index(0), Some(Fold::Up(7)));
+        assert_eq!(forward.num_points(), 17);
+        assert_eq!(forward.applied_fold_count(), 1);
+        assert_eq!(forward.apply_fold_by_index(0), Some(Fold::Left(5)));
+        assert_eq!(forward.applied_fold_count(), 2);
+
+        let mut reverse = Paper::parse_from_str(EXAMPLE_INPUT).unwrap();
+        assert_eq!(reverse.apply_fold_by_index(1), Some(Fold::Left(5)));
+        assert_eq!(reverse.pending_folds(), [Fold::Up(7)]);
+        assert_eq!(reverse.applied_fold_count(), 1);
+        // Different points overlap after only the x fold
+        assert_eq!(reverse.num_points(), 17);
+        assert_ne!(
+            reverse.points,
+            Paper::parse_from_str(EXAMPLE_INPUT)
+                .map(|mut paper| {
+                    paper.apply_fold();
+                    paper.points
+                })
+                .unwrap()
+        );
+
+        // Folds along different axes commute, so the end result is the same
+        assert_eq!(reverse.apply_fold_by_index(0), Some(Fold::Up(7)));
+        assert_eq!(reverse.applied_fold_count(), 2);
+        assert!(reverse.pending_folds().is_empty());
+        assert_eq!(reverse.points, forward.points);
+
+        assert_eq!(reverse.undo_fold(), Some(Fold::Up(7)));
+        assert_eq!(reverse.applied_fold_count(), 1);
+        assert_eq!(reverse.pending_folds(), [Fold::Up(7)]);
+    }
+
     #[test]
     fn test_apply_folds() {
         let mut paper = Paper::parse_from_str(EXAMPLE_INPUT).unwrap();
@@ -190,6 +427,127 @@ fold along x=5
         assert_eq!(paper.num_points(), 16);
     }
 
+    #[test]
+    fn test_fold_to_dimensions() {
+        let mut paper = Paper::parse_from_str(EXAMPLE_INPUT).unwrap();
+        assert_eq!(paper.bounds(), (11, 15));
+
+        // The first fold leaves the bottom two rows empty, so one fold is
+        // enough to fit
+        assert_eq!(paper.fold_to_dimensions(39, 5), 1);
+        let (width, height) = paper.bounds();
+        assert!(width <= 39 && height <= 5);
+
+        let mut paper = Paper::parse_from_str(EXAMPLE_INPUT).unwrap();
+        assert_eq!(paper.fold_to_dimensions(5, 5), 2);
+        assert_eq!(paper.bounds(), (5, 5));
+
+        // Already fits
+        let mut paper = Paper::parse_from_str(EXAMPLE_INPUT).unwrap();
+        assert_eq!(paper.fold_to_dimensions(11, 15), 0);
+
+        // Can't fit, so all folds are applied
+        assert_eq!(paper.fold_to_dimensions(1, 1), 2);
+        assert_eq!(paper.bounds(), (5, 5));
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let mut paper = Paper::parse_from_str(EXAMPLE_INPUT).unwrap();
+        assert_eq!(paper.bounding_box(), Some((0, 0, 10, 14)));
+
+        paper.apply_folds();
+        assert_eq!(paper.bounding_box(), Some((0, 0, 4, 4)));
+
+        let svg = paper.to_svg(10);
+        assert!(std::str::from_utf8(svg.as_bytes()).is_ok());
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<rect ").count(), 16);
+        assert!(svg.contains("viewBox=\"0 0 50 50\""));
+        assert!(svg.contains("<rect x=\"40\" y=\"20\" width=\"10\" height=\"10\"/>"));
+
+        let empty = Paper {
+            points: vec![],
+            folds: vec![],
+            applied_folds: vec![],
+        };
+        assert_eq!(empty.bounding_box(), None);
+        assert_eq!(empty.to_svg(10).matches("<rect ").count(), 0);
+    }
+
+    #[test]
+    fn test_undo_fold() {
+        let input = "0,0\n1,4\n3,3\n\nfold along y=2\n";
+        let mut paper = Paper::parse_from_str(input).unwrap();
+        let mut original = paper.points.clone();
+        original.sort_unstable();
+
+        assert_eq!(paper.undo_fold(), None);
+        assert_eq!(paper.apply_fold(), Some(Fold::Up(2)));
+        assert_eq!(paper.points, [(0, 0), (1, 0), (3, 1)]);
+
+        assert_eq!(paper.undo_fold(), Some(Fold::Up(2)));
+        assert_eq!(paper.num_points(), 3);
+        assert_eq!(paper.points, original);
+        assert_eq!(paper.undo_fold(), None);
+
+        // The fold can be applied again
+        assert_eq!(paper.apply_fold(), Some(Fold::Up(2)));
+        assert_eq!(paper.num_points(), 3);
+
+        // Points merged by a fold can't be recovered
+        let mut paper = Paper::parse_from_str(EXAMPLE_INPUT).unwrap();
+        paper.apply_folds();
+        assert_eq!(paper.undo_fold(), Some(Fold::Left(5)));
+        assert_eq!(paper.num_points(), 16);
+        assert_eq!(paper.undo_fold(), Some(Fold::Up(7)));
+        assert_eq!(paper.num_points(), 16);
+        assert_eq!(paper.bounds(), (11, 15));
+    }
+
+    #[test]
+    fn test_ocr() {
+        fn paper_from_pixels(rows: &[&str]) -> Paper {
+            let points = rows
+                .iter()
+                .enumerate()
+                .flat_map(|(y, row)| {
+                    row.chars()
+                        .enumerate()
+                        .filter(|&(_, c)| c == '#')
+                        .map(move |(x, _)| (x as i32, y as i32))
+                })
+                .collect();
+            Paper {
+                points,
+                folds: vec![],
+                applied_folds: vec![],
+            }
+        }
+
+        let paper = paper_from_pixels(&[
+            "#..#.####.#....#.....##.",
+            "#..#.#....#....#....#..#",
+            "####.###..#....#....#..#",
+            "#..#.#....#....#....#..#",
+            "#..#.#....#....#....#..#",
+            "#..#.####.####.####..##.",
+        ]);
+        assert_eq!(paper.ocr(), Ok("HELLO".to_string()));
+
+        let paper = paper_from_pixels(&FONT);
+        assert_eq!(paper.ocr(), Ok(FONT_LETTERS.to_string()));
+
+        // The example folds to a square, which isn't a letter
+        let mut paper = Paper::parse_from_str(EXAMPLE_INPUT).unwrap();
+        paper.apply_folds();
+        let err = paper.ocr().unwrap_err();
+        assert!(err.starts_with("Unrecognised letter"));
+
+        assert_eq!(paper_from_pixels(&[]).ocr(), Ok(String::new()));
+    }
+
     #[test]
     fn test_display() {
         let mut paper = Paper::parse_from_str(EXAMPLE_INPUT).unwrap();
@@ -1,4 +1,8 @@
-use std::{collections::HashMap, fs};
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    io::{self, BufRead},
+};
 
 use itertools::Itertools;
 
@@ -23,6 +27,46 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 pub struct PolymerInput {
     polymer: Vec<char>,
     rules: HashMap<(char, char), char>,
+    /// Left-hand sides which appeared in more than one rule. Only the last
+    /// such rule is kept in `rules`
+    duplicate_rules: Vec<(char, char)>,
+}
+
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    MissingPolymer,
+    /// A rule which is not of the form `AB -> C`, with its (1-based) line
+    /// number
+    InvalidRule {
+        line: usize,
+        content: String,
+    },
+    /// The (1-based) line which should have been blank
+    EmptyBlankLine {
+        line: usize,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(err) => write!(f, "Failed to read input: {}", err),
+            ParseError::MissingPolymer => write!(f, "Expected polymer on first line"),
+            ParseError::InvalidRule { line, content } => {
+                write!(f, "Invalid rule on line {}: {:?}", line, content)
+            }
+            ParseError::EmptyBlankLine { line } => write!(f, "Expected line {} to be blank", line),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<io::Error> for ParseError {
+    fn from(err: io::Error) -> Self {
+        ParseError::Io(err)
+    }
 }
 
 impl PolymerInput {
@@ -34,33 +78,147 @@ impl PolymerInput {
             return None;
         }
 
-        let rules = lines
-            .map(|line| {
-                let (from, to) = line.split_once(" -> ")?;
+        let parsed_rules = lines.map(Self::parse_rule).collect::<Option<Vec<_>>>()?;
 
-                let mut from_chars = from.chars();
-                let from = (from_chars.next()?, from_chars.next()?);
-                if from_chars.next().is_some() {
-                    return None;
-                }
+        Some(Self::from_parsed_rules(polymer, parsed_rules))
+    }
 
-                let mut to_chars = to.chars();
-                let to = to_chars.next()?;
-                if to_chars.next().is_some() {
-                    return None;
-                }
+    /// Like `parse_from_str`, but reports where the input is invalid. The
+    /// polymer must not be empty
+    pub fn parse_from_reader<R: BufRead>(reader: R) -> Result<PolymerInput, ParseError> {
+        let mut lines = reader.lines();
+
+        let polymer: Vec<_> = match lines.next().transpose()? {
+            Some(line) if !line.is_empty() => line.chars().collect(),
+            _ => return Err(ParseError::MissingPolymer),
+        };
+        match lines.next().transpose()? {
+            Some(line) if line.is_empty() => {}
+            _ => return Err(ParseError::EmptyBlankLine { line: 2 }),
+        }
+
+        let mut parsed_rules = Vec::new();
+        for (idx, line) in lines.enumerate() {
+            let line = line?;
+            let rule = Self::parse_rule(&line).ok_or_else(|| ParseError::InvalidRule {
+                // The rules start on the third line
+                line: idx + 3,
+                content: line.clone(),
+            })?;
+            parsed_rules.push(rule);
+        }
+
+        Ok(Self::from_parsed_rules(polymer, parsed_rules))
+    }
+
+    /// Parses a rule of the form `AB -> C`
+    fn parse_rule(line: &str) -> Option<((char, char), char)> {
+        let (from, to) = line.split_once(" -> ")?;
+
+        let mut from_chars = from.chars();
+        let from = (from_chars.next()?, from_chars.next()?);
+        if from_chars.next().is_some() {
+            return None;
+        }
+
+        let mut to_chars = to.chars();
+        let to = to_chars.next()?;
+        if to_chars.next().is_some() {
+            return None;
+        }
+
+        Some((from, to))
+    }
+
+    fn from_parsed_rules(polymer: Vec<char>, parsed_rules: Vec<((char, char), char)>) -> Self {
+        let mut rules = HashMap::new();
+        let mut duplicate_rules = Vec::new();
+        for (from, to) in parsed_rules {
+            if rules.insert(from, to).is_some() {
+                duplicate_rules.push(from);
+            }
+        }
+
+        Self {
+            polymer,
+            rules,
+            duplicate_rules,
+        }
+    }
+
+    /// Rules may insert characters not in the polymer, extending its alphabet.
+    /// Fails if the polymer is empty, or if any character is the marker
+    /// reserved by `PolymerGrower`
+    pub fn from_polymer_and_rules(
+        polymer: Vec<char>,
+        rules: HashMap<(char, char), char>,
+    ) -> Result<Self, String> {
+        if polymer.is_empty() {
+            return Err("Polymer must not be empty".to_owned());
+        }
 
-                Some((from, to))
-            })
-            .collect::<Option<HashMap<_, _>>>()?;
+        let reserved = PolymerGrower::MARKER_CHAR;
+        let rule_chars = rules.iter().flat_map(|(&(c1, c2), &to)| [c1, c2, to]);
+        if polymer
+            .iter()
+            .copied()
+            .chain(rule_chars)
+            .any(|c| c == reserved)
+        {
+            return Err(format!("Character {:?} is reserved", reserved));
+        }
 
-        Some(Self { polymer, rules })
+        Ok(Self {
+            polymer,
+            rules,
+            duplicate_rules: Vec::new(),
+        })
+    }
+
+    /// A polymer with no insertion rules, so it never grows
+    pub fn from_polymer(polymer: Vec<char>) -> Self {
+        Self {
+            polymer,
+            rules: HashMap::new(),
+            duplicate_rules: Vec::new(),
+        }
+    }
+
+    /// Checks that no two rules share a left-hand side, and that every
+    /// character of the polymer is part of at least one rule's pair. Rules
+    /// inserting anything other than a single character are already rejected
+    /// when parsing
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(&(c1, c2)) = self.duplicate_rules.first() {
+            return Err(format!("Multiple rules for pair '{}{}'", c1, c2));
+        }
+
+        for &c in &self.polymer {
+            if !self.rules.keys().any(|&(c1, c2)| c1 == c || c2 == c) {
+                return Err(format!(
+                    "Polymer character '{}' does not appear in any rule",
+                    c
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn rule_count(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn polymer_len(&self) -> usize {
+        self.polymer.len()
     }
 }
 
 pub struct PolymerGrower {
     polymer_triple_counts: HashMap<(char, char, char), usize>,
+    initial_triple_counts: HashMap<(char, char, char), usize>,
     rules: HashMap<(char, char), char>,
+    steps_grown: usize,
 }
 
 impl From<PolymerInput> for PolymerGrower {
@@ -88,8 +246,10 @@ impl From<PolymerInput> for PolymerGrower {
         }
 
         Self {
+            initial_triple_counts: polymer_triple_counts.clone(),
             polymer_triple_counts,
             rules: input.rules,
+            steps_grown: 0,
         }
     }
 }
@@ -131,6 +291,18 @@ impl PolymerGrower {
             std::mem::swap(&mut new_triples, &mut self.polymer_triple_counts);
             new_triples.clear();
         }
+        self.steps_grown += steps;
+    }
+
+    /// Restores the polymer to its state before any growth
+    pub fn reset(&mut self) {
+        self.polymer_triple_counts = self.initial_triple_counts.clone();
+        self.steps_grown = 0;
+    }
+
+    /// Total number of steps grown since creation or the last `reset`
+    pub fn steps_grown(&self) -> usize {
+        self.steps_grown
     }
 
     #[cfg(test)]
@@ -139,7 +311,8 @@ impl PolymerGrower {
         self.polymer_triple_counts.values().sum::<usize>() - 2
     }
 
-    pub fn polymer_score(&self) -> Option<usize> {
+    /// Number of occurrences of each character in the current polymer
+    pub fn char_counts(&self) -> HashMap<char, usize> {
         let mut counts: HashMap<char, usize> = HashMap::new();
 
         for (&(c1, c2, c3), &count) in self.polymer_triple_counts.iter() {
@@ -150,16 +323,194 @@ impl PolymerGrower {
 
         counts.remove(&Self::MARKER_CHAR);
 
+        // Divide by 3 because (due to the special triples we added) each
+        // character in the polymer is counted three times (in three different
+        // triples)
+        counts.values_mut().for_each(|count| *count /= 3);
         counts
-            .iter()
-            .map(|(_, count)| *count)
+    }
+
+    /// Ties are broken by choosing the earliest character
+    pub fn most_common(&self) -> Option<(char, usize)> {
+        self.n_most_common(1).pop()
+    }
+
+    /// Ties are broken by choosing the earliest character
+    pub fn least_common(&self) -> Option<(char, usize)> {
+        self.char_counts()
+            .into_iter()
+            .min_by_key(|&(c, count)| (count, c))
+    }
+
+    /// The `n` most common characters, sorted by descending count and then by
+    /// character
+    pub fn n_most_common(&self, n: usize) -> Vec<(char, usize)> {
+        let mut counts: Vec<_> = self.char_counts().into_iter().collect();
+        counts.sort_unstable_by(|(c1, count1), (c2, count2)| {
+            count2.cmp(count1).then_with(|| c1.cmp(c2))
+        });
+        counts.truncate(n);
+        counts
+    }
+
+    pub fn polymer_score(&self) -> Option<usize> {
+        self.char_counts()
+            .values()
+            .copied()
             .minmax()
             .into_option()
-            // Divide by 3 because (due to the special triples we added) each
-            // character in the polymer is counted three times (in three
-            // different triples)
-            .map(|(min, max)| (max - min) / 3)
+            .map(|(min, max)| max - min)
+    }
+}
+
+/// Builds the matrix `M` such that if `v` holds the number of occurrences of
+/// each pair in `pairs`, then `v * M` holds the counts after a single step.
+/// Pairs without a matching rule are left as is
+pub fn build_growth_matrix(
+    rules: &HashMap<(char, char), char>,
+    pairs: &[(char, char)],
+) -> Vec<Vec<u64>> {
+    let index: HashMap<_, _> = pairs.iter().enumerate().map(|(i, &p)| (p, i)).collect();
+
+    let mut matrix = vec![vec![0; pairs.len()]; pairs.len()];
+    for (i, &(c1, c2)) in pairs.iter().enumerate() {
+        match rules.get(&(c1, c2)) {
+            Some(&to) => {
+                for pair in [(c1, to), (to, c2)] {
+                    if let Some(&j) = index.get(&pair) {
+                        matrix[i][j] += 1;
+                    }
+                }
+            }
+            None => matrix[i][i] += 1,
+        }
     }
+    matrix
+}
+
+fn matrix_mul(lhs: &[Vec<u64>], rhs: &[Vec<u64>]) -> Vec<Vec<u64>> {
+    let n = lhs.len();
+    let mut result = vec![vec![0; n]; n];
+    for i in 0..n {
+        for k in 0..n {
+            if lhs[i][k] == 0 {
+                continue;
+            }
+            for j in 0..n {
+                result[i][j] += lhs[i][k] * rhs[k][j];
+            }
+        }
+    }
+    result
+}
+
+/// Raises a square matrix to the power `n` by repeated squaring
+pub fn matrix_pow(matrix: &[Vec<u64>], mut n: usize) -> Vec<Vec<u64>> {
+    let size = matrix.len();
+    let mut result: Vec<Vec<u64>> = (0..size)
+        .map(|i| (0..size).map(|j| (i == j) as u64).collect())
+        .collect();
+    let mut base = matrix.to_vec();
+    while n > 0 {
+        if n % 2 == 1 {
+            result = matrix_mul(&result, &base);
+        }
+        n /= 2;
+        if n > 0 {
+            base = matrix_mul(&base, &base);
+        }
+    }
+    result
+}
+
+/// Computes the polymer score after `steps` steps using the growth matrix.
+/// Should give the same result as `PolymerGrower`
+pub fn simulate_with_matrix(input: &PolymerInput, steps: usize) -> u64 {
+    let alphabet: Vec<char> = input
+        .polymer
+        .iter()
+        .copied()
+        .chain(input.rules.iter().flat_map(|(&(c1, c2), &to)| [c1, c2, to]))
+        .sorted()
+        .dedup()
+        .collect();
+    let pairs: Vec<_> = alphabet
+        .iter()
+        .copied()
+        .cartesian_product(alphabet.iter().copied())
+        .collect();
+
+    let mut initial = vec![0; pairs.len()];
+    for pair in input.polymer.windows(2) {
+        let idx = pairs.iter().position(|&p| p == (pair[0], pair[1])).unwrap();
+        initial[idx] += 1;
+    }
+
+    let matrix = matrix_pow(&build_growth_matrix(&input.rules, &pairs), steps);
+    let mut counts: HashMap<char, u64> = HashMap::new();
+    for (i, &count) in initial.iter().enumerate() {
+        for (j, &(c1, _)) in pairs.iter().enumerate() {
+            *counts.entry(c1).or_insert(0) += count * matrix[i][j];
+        }
+    }
+
+    // Each character is counted as the first of a pair, except for the last
+    // character of the polymer (which never changes)
+    if let Some(&last) = input.polymer.last() {
+        *counts.entry(last).or_insert(0) += 1;
+    }
+
+    counts
+        .values()
+        .copied()
+        .filter(|&count| count > 0)
+        .minmax()
+        .into_option()
+        .map(|(min, max)| max - min)
+        .unwrap_or(0)
+}
+
+/// Default limit on the length of the polymer built by
+/// `polymer_string_after_n_steps`
+pub const MAX_POLYMER_STRING_LEN: usize = 1_000_000;
+
+/// Builds the actual polymer after `steps` steps by inserting characters one
+/// by one. Returns `None` if it would exceed `MAX_POLYMER_STRING_LEN`
+pub fn polymer_string_after_n_steps(input: &PolymerInput, steps: usize) -> Option<String> {
+    polymer_string_after_n_steps_with_limit(input, steps, MAX_POLYMER_STRING_LEN)
+}
+
+/// Like `polymer_string_after_n_steps`, but with a custom length limit
+pub fn polymer_string_after_n_steps_with_limit(
+    input: &PolymerInput,
+    steps: usize,
+    max_len: usize,
+) -> Option<String> {
+    if input.polymer.len() > max_len {
+        return None;
+    }
+
+    let mut polymer = input.polymer.clone();
+    for _ in 0..steps {
+        let insertions = polymer
+            .windows(2)
+            .filter(|pair| input.rules.contains_key(&(pair[0], pair[1])))
+            .count();
+        if polymer.len() + insertions > max_len {
+            return None;
+        }
+
+        let mut next = Vec::with_capacity(polymer.len() + insertions);
+        for pair in polymer.windows(2) {
+            next.push(pair[0]);
+            if let Some(&to) = input.rules.get(&(pair[0], pair[1])) {
+                next.push(to);
+            }
+        }
+        next.extend(polymer.last());
+        polymer = next;
+    }
+    Some(polymer.into_iter().collect())
 }
 
 #[cfg(test)]
@@ -197,6 +548,54 @@ CN -> C
         assert_eq!(input.rules.get(&('A', 'S')), None);
     }
 
+    #[test]
+    fn test_parse_from_reader() {
+        let input = PolymerInput::parse_from_reader(io::Cursor::new(EXAMPLE_INPUT)).unwrap();
+        let expected = PolymerInput::parse_from_str(EXAMPLE_INPUT).unwrap();
+        assert_eq!(input.polymer, expected.polymer);
+        assert_eq!(input.rules, expected.rules);
+
+        let malformed = EXAMPLE_INPUT.replacen("HH -> N", "HH => N", 1);
+        let result = PolymerInput::parse_from_reader(io::Cursor::new(malformed));
+        match result {
+            Err(err @ ParseError::InvalidRule { .. }) => {
+                assert_eq!(err.to_string(), "Invalid rule on line 4: \"HH => N\"");
+            }
+            _ => panic!("expected an invalid rule"),
+        }
+
+        let result = PolymerInput::parse_from_reader(io::Cursor::new(""));
+        assert!(matches!(result, Err(ParseError::MissingPolymer)));
+        let result = PolymerInput::parse_from_reader(io::Cursor::new("NNCB\nCH -> B\n"));
+        assert!(matches!(
+            result,
+            Err(ParseError::EmptyBlankLine { line: 2 })
+        ));
+        let result = PolymerInput::parse_from_reader(io::Cursor::new(b"NNCB\n\n\xff".as_ref()));
+        assert!(matches!(result, Err(ParseError::Io(_))));
+    }
+
+    #[test]
+    fn test_validate() {
+        let input = PolymerInput::parse_from_str(EXAMPLE_INPUT).unwrap();
+        assert_eq!(input.validate(), Ok(()));
+        assert_eq!(input.rule_count(), 16);
+        assert_eq!(input.polymer_len(), 4);
+
+        let duplicated = format!("{}CH -> N\n", EXAMPLE_INPUT);
+        let input = PolymerInput::parse_from_str(&duplicated).unwrap();
+        assert_eq!(input.rule_count(), 16);
+        assert!(input.validate().is_err());
+
+        let mismatched = EXAMPLE_INPUT.replacen("NNCB", "NNXB", 1);
+        let input = PolymerInput::parse_from_str(&mismatched).unwrap();
+        assert!(input.validate().is_err());
+
+        // Rules must insert exactly one character
+        let long_insert = EXAMPLE_INPUT.replacen("CH -> B", "CH -> BB", 1);
+        assert!(PolymerInput::parse_from_str(&long_insert).is_none());
+    }
+
     #[test]
     fn test_grow() {
         let mut grower: PolymerGrower = PolymerInput::parse_from_str(EXAMPLE_INPUT).unwrap().into();
@@ -220,4 +619,137 @@ CN -> C
         grower.grow(40 - 10);
         assert_eq!(grower.polymer_score(), Some(2188189693529));
     }
+
+    #[test]
+    fn test_from_polymer_and_rules() {
+        let parsed = PolymerInput::parse_from_str(EXAMPLE_INPUT).unwrap();
+        let input =
+            PolymerInput::from_polymer_and_rules(parsed.polymer.clone(), parsed.rules.clone())
+                .unwrap();
+        let mut expected: PolymerGrower = parsed.into();
+        let mut grower: PolymerGrower = input.into();
+        for _ in 0..10 {
+            expected.grow(1);
+            grower.grow(1);
+            assert_eq!(grower.char_counts(), expected.char_counts());
+        }
+        assert_eq!(grower.polymer_score(), Some(1588));
+
+        let polymer: Vec<_> = "NNCB".chars().collect();
+        let input = PolymerInput::from_polymer(polymer.clone());
+        assert_eq!(input.rule_count(), 0);
+        let mut grower: PolymerGrower = input.into();
+        grower.grow(10);
+        assert_eq!(grower.polymer_len(), 4);
+        assert_eq!(
+            grower.char_counts(),
+            HashMap::from([('N', 2), ('C', 1), ('B', 1)])
+        );
+
+        // Rules can introduce new characters
+        let rules = HashMap::from([(('N', 'C'), 'Z')]);
+        let input = PolymerInput::from_polymer_and_rules(polymer.clone(), rules).unwrap();
+        let mut grower: PolymerGrower = input.into();
+        grower.grow(1);
+        assert_eq!(grower.char_counts()[&'Z'], 1);
+
+        assert!(PolymerInput::from_polymer_and_rules(vec![], HashMap::new()).is_err());
+        let rules = HashMap::from([(('N', 'C'), '\n')]);
+        assert!(PolymerInput::from_polymer_and_rules(polymer, rules).is_err());
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut grower: PolymerGrower = PolymerInput::parse_from_str(EXAMPLE_INPUT).unwrap().into();
+
+        grower.grow(10);
+        assert_eq!(grower.steps_grown(), 10);
+        assert_eq!(grower.polymer_score(), Some(1588));
+
+        grower.reset();
+        assert_eq!(grower.steps_grown(), 0);
+        assert_eq!(grower.polymer_len(), 4);
+
+        grower.grow(4);
+        grower.grow(6);
+        assert_eq!(grower.steps_grown(), 10);
+        assert_eq!(grower.polymer_score(), Some(1588));
+    }
+
+    #[test]
+    fn test_char_counts() {
+        let mut grower: PolymerGrower = PolymerInput::parse_from_str(EXAMPLE_INPUT).unwrap().into();
+        assert_eq!(
+            grower.char_counts(),
+            HashMap::from([('N', 2), ('C', 1), ('B', 1)])
+        );
+        assert_eq!(grower.most_common(), Some(('N', 2)));
+        assert_eq!(grower.least_common(), Some(('B', 1)));
+
+        grower.grow(10);
+        assert_eq!(grower.most_common(), Some(('B', 1749)));
+        assert_eq!(grower.least_common(), Some(('H', 161)));
+        assert_eq!(grower.char_counts().values().sum::<usize>(), 3073);
+        assert_eq!(grower.n_most_common(2), vec![('B', 1749), ('N', 865)]);
+        assert_eq!(grower.n_most_common(10).len(), 4);
+
+        grower.grow(30);
+        assert_eq!(grower.most_common(), Some(('B', 2192039569602)));
+        assert_eq!(grower.least_common(), Some(('H', 3849876073)));
+    }
+
+    #[test]
+    fn test_growth_matrix() {
+        let rules = HashMap::from([(('A', 'B'), 'A')]);
+        let pairs = [('A', 'A'), ('A', 'B'), ('B', 'A')];
+        let matrix = build_growth_matrix(&rules, &pairs);
+        // AB -> AA + AB, others are unchanged
+        assert_eq!(matrix, vec![vec![1, 0, 0], vec![1, 1, 0], vec![0, 0, 1]]);
+
+        assert_eq!(
+            matrix_pow(&matrix, 0),
+            vec![vec![1, 0, 0], vec![0, 1, 0], vec![0, 0, 1]]
+        );
+        assert_eq!(matrix_pow(&matrix, 1), matrix);
+        assert_eq!(
+            matrix_pow(&matrix, 5),
+            vec![vec![1, 0, 0], vec![5, 1, 0], vec![0, 0, 1]]
+        );
+    }
+
+    #[test]
+    fn test_simulate_with_matrix() {
+        let input = PolymerInput::parse_from_str(EXAMPLE_INPUT).unwrap();
+        assert_eq!(simulate_with_matrix(&input, 10), 1588);
+        assert_eq!(simulate_with_matrix(&input, 40), 2188189693529);
+
+        let mut grower: PolymerGrower = PolymerInput::parse_from_str(EXAMPLE_INPUT).unwrap().into();
+        for steps in [1, 2, 5] {
+            grower.grow(steps);
+        }
+        let score = grower.polymer_score().unwrap() as u64;
+        assert_eq!(simulate_with_matrix(&input, 8), score);
+    }
+
+    #[test]
+    fn test_polymer_string_after_n_steps() {
+        let input = PolymerInput::parse_from_str(EXAMPLE_INPUT).unwrap();
+        assert_eq!(polymer_string_after_n_steps(&input, 0).unwrap(), "NNCB");
+        assert_eq!(polymer_string_after_n_steps(&input, 1).unwrap(), "NCNBCHB");
+        assert_eq!(
+            polymer_string_after_n_steps(&input, 2).unwrap(),
+            "NBCCNBBBCBHCB"
+        );
+        assert_eq!(
+            polymer_string_after_n_steps(&input, 10).unwrap().len(),
+            3073
+        );
+        assert_eq!(polymer_string_after_n_steps(&input, 100), None);
+
+        assert_eq!(
+            polymer_string_after_n_steps_with_limit(&input, 1, 7).unwrap(),
+            "NCNBCHB"
+        );
+        assert_eq!(polymer_string_after_n_steps_with_limit(&input, 2, 12), None);
+    }
 }
@@ -1,7 +1,9 @@
+use rust_aoc_2021::util::grid;
 use std::{
     cmp::Ordering,
-    collections::{BinaryHeap, HashSet},
+    collections::{BinaryHeap, HashMap, HashSet},
     fs,
+    ops::RangeInclusive,
 };
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -21,69 +23,116 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 #[derive(Clone)]
-struct Grid {
-    width: i32,
-    height: i32,
-    grid: Vec<u8>,
+pub struct Grid {
+    grid: grid::Grid<u8>,
 }
 
 impl Grid {
-    const TILE_COUNT: i32 = 5;
+    /// Number of times the grid is repeated in each direction when tiled
+    const DEFAULT_TILE_COUNT: u32 = 5;
+
+    /// Risk levels allowed in a cell. Tiling wraps around within this range,
+    /// and the searches rely on every cell having a positive risk
+    const RISK_RANGE: RangeInclusive<u8> = 1..=9;
 
     pub fn parse_from_str(input: &str) -> Option<Self> {
-        let width = input.find('\n')? as i32;
-        if width == 0 {
+        let (width, height, grid) = grid::parse_digit_grid(input)?;
+        if !grid.iter().all(|risk| Self::RISK_RANGE.contains(risk)) {
             return None;
         }
+        Some(Self {
+            grid: grid::Grid::new(width, height, grid),
+        })
+    }
 
-        let grid = input
-            .chars()
-            .filter(|c| !c.is_whitespace())
-            .map(|c| Some(c.to_digit(10)? as u8))
-            .collect::<Option<Vec<u8>>>()?;
-        let height = grid.len() as i32 / width;
+    /// Creates a grid where the risk level at `(x, y)` is `f(x, y)`. Returns
+    /// `None` if any risk level is outside `1..=9`
+    pub fn from_fn(width: usize, height: usize, f: impl Fn(i32, i32) -> u8) -> Option<Self> {
+        let grid = (0..height as i32)
+            .flat_map(|y| (0..width as i32).map(move |x| (x, y)))
+            .map(|(x, y)| Some(f(x, y)).filter(|risk| Self::RISK_RANGE.contains(risk)))
+            .collect::<Option<Vec<_>>>()?;
         Some(Self {
-            width,
-            height,
-            grid,
+            grid: grid::Grid::new(width, height, grid),
         })
     }
 
-    fn get_at(&self, x: i32, y: i32, tiled: bool) -> Option<u8> {
+    /// Sets the risk level at `(x, y)` in the untiled grid. Returns `false` if
+    /// out of bounds or if `value` is outside `1..=9`
+    pub fn set_at(&mut self, x: i32, y: i32, value: u8) -> bool {
+        if !Self::RISK_RANGE.contains(&value) {
+            return false;
+        }
+        match self.grid.get_mut(x, y) {
+            Some(cell) => {
+                *cell = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn width(&self) -> i32 {
+        self.grid.width() as i32
+    }
+
+    fn height(&self) -> i32 {
+        self.grid.height() as i32
+    }
+
+    fn tile_count(tiled: bool) -> u32 {
         if tiled {
-            return self.get_at_tiled(x, y);
+            Self::DEFAULT_TILE_COUNT
+        } else {
+            1
         }
+    }
 
-        if x < 0 || x >= self.width || y < 0 || y >= self.height {
-            return None;
+    fn get_at(&self, x: i32, y: i32, tile_count: u32) -> Option<u8> {
+        if tile_count != 1 {
+            return self.get_at_tiled(x, y, tile_count);
         }
-        Some(self.grid[(y * self.width + x) as usize])
+
+        self.grid.get(x, y).copied()
     }
 
-    fn get_at_tiled(&self, x: i32, y: i32) -> Option<u8> {
-        if x < 0
-            || x >= Self::TILE_COUNT * self.width
-            || y < 0
-            || y >= Self::TILE_COUNT * self.height
-        {
+    /// Value at `(x, y)` with the grid repeated `tile_count` times in each
+    /// direction, each repetition having its values increased (wrapping from 9
+    /// to 1) by its distance in tiles from the top left
+    fn get_at_tiled(&self, x: i32, y: i32, tile_count: u32) -> Option<u8> {
+        let (tiled_width, tiled_height) = self.dimensions(tile_count);
+        if x < 0 || x >= tiled_width || y < 0 || y >= tiled_height {
             return None;
         }
 
-        let tile_x = x / self.width;
-        let tile_y = y / self.height;
+        let (width, height) = (self.width(), self.height());
+
+        let tile_x = x / width;
+        let tile_y = y / height;
 
-        let x = x - self.width * tile_x;
-        let y = y - self.height * tile_y;
+        let x = x - width * tile_x;
+        let y = y - height * tile_y;
 
-        let original_value = self.grid[(y * self.width + x) as usize];
-        Some((original_value - 1 + tile_x as u8 + tile_y as u8) % 9 + 1)
+        let original_value = *self.grid.get(x, y)? as i32;
+        Some(((original_value - 1 + tile_x + tile_y) % 9 + 1) as u8)
     }
 
-    // We use Uniform Cost Search
+    /// Lowest total risk of the grid on its own, or when tiled as described by
+    /// `get_at_tiled` with the default tile count
     pub fn lowest_total_risk(&self, tiled: bool) -> Option<u32> {
+        self.lowest_total_risk_tiled(Self::tile_count(tiled))
+    }
+
+    pub fn lowest_total_risk_unscaled(&self) -> Option<u32> {
+        self.lowest_total_risk_tiled(1)
+    }
+
+    // We use Uniform Cost Search
+    pub fn lowest_total_risk_tiled(&self, tile_count: u32) -> Option<u32> {
         let node = Node {
             grid: self,
             total_cost: 0,
+            heuristic_cost: 0,
             x: 0,
             y: 0,
         };
@@ -95,13 +144,13 @@ impl Grid {
         while let Some(node) = frontier.pop() {
             added.remove(&(node.x, node.y));
 
-            if node.is_goal(tiled) {
+            if node.is_goal(tile_count) {
                 return Some(node.total_cost);
             }
 
             explored.insert((node.x, node.y));
 
-            for neighbor in node.neighbors(tiled) {
+            for neighbor in node.neighbors(tile_count) {
                 if !explored.contains(&(neighbor.x, neighbor.y))
                     && !added.contains(&(neighbor.x, neighbor.y))
                 {
@@ -113,19 +162,189 @@ impl Grid {
 
         None
     }
+
+    pub fn lowest_total_risk_astar(&self, tiled: bool) -> Option<u32> {
+        self.lowest_total_risk_with_path(tiled)
+            .map(|(risk, _)| risk)
+    }
+
+    /// Like `lowest_total_risk`, but also returns the coordinates along the
+    /// path taken (including start and end). Uses A* search with a Manhattan
+    /// distance heuristic
+    pub fn lowest_total_risk_with_path(&self, tiled: bool) -> Option<(u32, Vec<(i32, i32)>)> {
+        let tile_count = Self::tile_count(tiled);
+        let mut node = Node {
+            grid: self,
+            total_cost: 0,
+            heuristic_cost: 0,
+            x: 0,
+            y: 0,
+        };
+        node.heuristic_cost = node.distance_to_goal(tile_count);
+
+        let mut frontier: BinaryHeap<Node> = BinaryHeap::from([node]);
+        let mut best_costs = HashMap::from([((0, 0), 0)]);
+        let mut parents: HashMap<(i32, i32), (i32, i32)> = HashMap::new();
+
+        while let Some(node) = frontier.pop() {
+            if node.total_cost > best_costs[&(node.x, node.y)] {
+                // Stale entry, a cheaper path to this node was already found
+                continue;
+            }
+
+            if node.is_goal(tile_count) {
+                let mut path = vec![(node.x, node.y)];
+                while let Some(&parent) = parents.get(&path[path.len() - 1]) {
+                    path.push(parent);
+                }
+                path.reverse();
+                return Some((node.total_cost, path));
+            }
+
+            for mut neighbor in node.neighbors(tile_count) {
+                let pos = (neighbor.x, neighbor.y);
+                if best_costs
+                    .get(&pos)
+                    .map(|&best| neighbor.total_cost < best)
+                    .unwrap_or(true)
+                {
+                    best_costs.insert(pos, neighbor.total_cost);
+                    parents.insert(pos, (node.x, node.y));
+                    neighbor.heuristic_cost = neighbor.distance_to_goal(tile_count);
+                    frontier.push(neighbor);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Counts the distinct paths which achieve the lowest total risk, or
+    /// returns `None` if there is no path. The count saturates at
+    /// `usize::MAX`, which even a modest grid of equal risks can exceed
+    pub fn count_lowest_risk_paths(&self, tiled: bool) -> Option<usize> {
+        let tile_count = Self::tile_count(tiled);
+        let node = Node {
+            grid: self,
+            total_cost: 0,
+            heuristic_cost: 0,
+            x: 0,
+            y: 0,
+        };
+
+        // Lowest cost found so far to each cell, and the number of paths
+        // achieving it
+        let mut best: HashMap<(i32, i32), (u32, usize)> = HashMap::from([((0, 0), (0, 1))]);
+        let mut frontier: BinaryHeap<Node> = BinaryHeap::from([node]);
+
+        while let Some(node) = frontier.pop() {
+            let (best_cost, path_count) = best[&(node.x, node.y)];
+            if node.total_cost > best_cost {
+                // Stale entry, a cheaper path to this node was already found
+                continue;
+            }
+
+            if node.is_goal(tile_count) {
+                return Some(path_count);
+            }
+
+            // Every cell has a positive risk, so all paths to this node with
+            // the lowest cost have already been counted
+            for neighbor in node.neighbors(tile_count) {
+                let pos = (neighbor.x, neighbor.y);
+                match best.get_mut(&pos) {
+                    Some((cost, count)) if neighbor.total_cost == *cost => {
+                        *count = count.saturating_add(path_count)
+                    }
+                    Some((cost, _)) if neighbor.total_cost > *cost => {}
+                    _ => {
+                        best.insert(pos, (neighbor.total_cost, path_count));
+                        frontier.push(neighbor);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    fn dimensions(&self, tile_count: u32) -> (i32, i32) {
+        let tile_count = tile_count as i32;
+        (tile_count * self.width(), tile_count * self.height())
+    }
+
+    /// Descriptive statistics over the (untiled) cell values
+    pub fn risk_statistics(&self) -> GridRiskStats {
+        let values = self.grid.data();
+        let n = values.len() as f64;
+        let mean = values.iter().map(|&v| v as f64).sum::<f64>() / n;
+        let central_moment = |k| {
+            values
+                .iter()
+                .map(|&v| (v as f64 - mean).powi(k))
+                .sum::<f64>()
+                / n
+        };
+
+        let variance = central_moment(2);
+        let skewness = if variance == 0. {
+            0.
+        } else {
+            central_moment(3) / variance.powf(1.5)
+        };
+
+        GridRiskStats {
+            mean,
+            variance,
+            skewness,
+            min: values.iter().copied().min().unwrap_or(0),
+            max: values.iter().copied().max().unwrap_or(0),
+        }
+    }
+
+    /// Shannon entropy (in bits) of the distribution of (untiled) cell values
+    pub fn risk_entropy(&self) -> f64 {
+        let mut counts = [0usize; 10];
+        for &value in self.grid.data() {
+            counts[value as usize] += 1;
+        }
+
+        let n = self.grid.data().len() as f64;
+        counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / n;
+                -p * p.log2()
+            })
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridRiskStats {
+    pub mean: f64,
+    pub variance: f64,
+    /// Third standardized moment (zero if all values are equal)
+    pub skewness: f64,
+    pub min: u8,
+    pub max: u8,
 }
 
 #[derive(Clone)]
 struct Node<'grid> {
     grid: &'grid Grid,
     total_cost: u32,
+    /// Estimate of the remaining cost to the goal (zero for uniform cost
+    /// search)
+    heuristic_cost: u32,
     x: i32,
     y: i32,
 }
 
 impl<'grid> PartialEq for Node<'grid> {
     fn eq(&self, other: &Self) -> bool {
-        self.total_cost == other.total_cost && self.x == other.x && self.y == other.y
+        self.estimated_cost() == other.estimated_cost() && self.x == other.x && self.y == other.y
     }
 }
 
@@ -135,8 +354,8 @@ impl<'grid> Ord for Node<'grid> {
     fn cmp(&self, other: &Self) -> Ordering {
         // Invert so as to get a min heap
         other
-            .total_cost
-            .cmp(&self.total_cost)
+            .estimated_cost()
+            .cmp(&self.estimated_cost())
             .then_with(|| self.x.cmp(&other.x))
             .then_with(|| self.y.cmp(&other.y))
     }
@@ -151,36 +370,34 @@ impl<'grid> PartialOrd for Node<'grid> {
 impl<'grid> Node<'grid> {
     const OFFSETS: &'static [(i32, i32)] = &[(-1, 0), (1, 0), (0, -1), (0, 1)];
 
-    fn is_goal(&self, tiled: bool) -> bool {
-        let width = if tiled {
-            Grid::TILE_COUNT * self.grid.width
-        } else {
-            self.grid.width
-        };
+    fn estimated_cost(&self) -> u32 {
+        self.total_cost + self.heuristic_cost
+    }
 
-        let len = if tiled {
-            self.grid.grid.len() as i32 * Grid::TILE_COUNT * Grid::TILE_COUNT
-        } else {
-            self.grid.grid.len() as i32
-        };
+    /// Manhattan distance to the goal. Since every cell costs at least 1 this
+    /// never overestimates the remaining cost
+    fn distance_to_goal(&self, tile_count: u32) -> u32 {
+        let (width, height) = self.grid.dimensions(tile_count);
+        ((width - 1 - self.x) + (height - 1 - self.y)) as u32
+    }
 
-        self.y * width + self.x == len - 1
+    fn is_goal(&self, tile_count: u32) -> bool {
+        let (width, height) = self.grid.dimensions(tile_count);
+        self.x == width - 1 && self.y == height - 1
     }
 
-    fn neighbors(&self, tiled: bool) -> impl Iterator<Item = Node<'grid>> + '_ {
-        Self::OFFSETS
-            .iter()
-            .map(move |(dx, dy)| {
-                let x = self.x + dx;
-                let y = self.y + dy;
-                self.grid.get_at(x, y, tiled).map(|cost| Node {
-                    grid: self.grid,
-                    total_cost: self.total_cost + cost as u32,
-                    x,
-                    y,
-                })
+    fn neighbors(&self, tile_count: u32) -> impl Iterator<Item = Node<'grid>> + '_ {
+        Self::OFFSETS.iter().filter_map(move |(dx, dy)| {
+            let x = self.x + dx;
+            let y = self.y + dy;
+            self.grid.get_at(x, y, tile_count).map(|cost| Node {
+                grid: self.grid,
+                total_cost: self.total_cost + cost as u32,
+                heuristic_cost: 0,
+                x,
+                y,
             })
-            .flatten()
+        })
     }
 }
 
@@ -203,24 +420,57 @@ mod tests {
     #[test]
     fn test_parse_input() {
         let grid = Grid::parse_from_str(TEST_INPUT).unwrap();
-        assert_eq!(grid.width, 10);
-        assert_eq!(grid.height, 10);
+        assert_eq!(grid.width(), 10);
+        assert_eq!(grid.height(), 10);
         assert_eq!(&grid.grid[..13], [1, 1, 6, 3, 7, 5, 1, 7, 4, 2, 1, 3, 8]);
+
+        // Rows of different lengths
+        assert!(Grid::parse_from_str("12\n345\n").is_none());
+    }
+
+    #[test]
+    fn test_from_fn_set_at() {
+        let mut grid = Grid::from_fn(3, 3, |x, y| (1 + x + 3 * y) as u8).unwrap();
+        assert_eq!(grid.grid.data(), &[1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        assert!(grid.set_at(1, 1, 7));
+        assert!(!grid.set_at(3, 1, 7));
+        assert!(!grid.set_at(-1, 0, 7));
+        assert!(!grid.set_at(0, 0, 0));
+        assert!(!grid.set_at(0, 0, 10));
+        assert_eq!(grid.get_at(1, 1, 1), Some(7));
+        assert_eq!(grid.grid.data(), &[1, 2, 3, 4, 7, 6, 7, 8, 9]);
+
+        // The tiled view follows the change
+        assert_eq!(grid.get_at(1, 4, 5), Some(8));
+
+        // Risk levels must be between 1 and 9
+        assert!(Grid::from_fn(1, 1, |_, _| 10).is_none());
+        assert!(Grid::from_fn(2, 1, |x, _| x as u8).is_none());
+        assert!(Grid::parse_from_str("10\n11\n").is_none());
+
+        // A cheap route along the top and right edges
+        let mut grid = Grid::from_fn(3, 3, |_, _| 9).unwrap();
+        for (x, y) in [(1, 0), (2, 0), (2, 1), (2, 2)] {
+            grid.set_at(x, y, 1);
+        }
+        assert_eq!(grid.lowest_total_risk(false), Some(4));
+        assert_eq!(grid.lowest_total_risk_astar(false), Some(4));
     }
 
     #[test]
     fn test_get_at() {
         let grid = Grid::parse_from_str(TEST_INPUT).unwrap();
-        assert_eq!(grid.get_at(0, 0, false), Some(1));
-        assert_eq!(grid.get_at(0, 0, true), Some(1));
-        assert_eq!(grid.get_at(2, 1, false), Some(8));
-        assert_eq!(grid.get_at(2, 1, true), Some(8));
-
-        assert_eq!(grid.get_at(3, 6, false), Some(9));
-        assert_eq!(grid.get_at(3, 6, true), Some(9));
-        assert_eq!(grid.get_at(10 * 3 + 3, 10 * 2 + 6, false), None);
+        assert_eq!(grid.get_at(0, 0, 1), Some(1));
+        assert_eq!(grid.get_at(0, 0, 5), Some(1));
+        assert_eq!(grid.get_at(2, 1, 1), Some(8));
+        assert_eq!(grid.get_at(2, 1, 5), Some(8));
+
+        assert_eq!(grid.get_at(3, 6, 1), Some(9));
+        assert_eq!(grid.get_at(3, 6, 5), Some(9));
+        assert_eq!(grid.get_at(10 * 3 + 3, 10 * 2 + 6, 1), None);
         // 9 + 5 = 14, 10 -> 1 so 14 -> 5
-        assert_eq!(grid.get_at(10 * 3 + 3, 10 * 2 + 6, true), Some(5));
+        assert_eq!(grid.get_at(10 * 3 + 3, 10 * 2 + 6, 5), Some(5));
     }
 
     #[test]
@@ -233,4 +483,134 @@ mod tests {
         let tiled_risk = grid.lowest_total_risk(true);
         assert_eq!(tiled_risk, Some(315));
     }
+
+    #[test]
+    fn test_tile_count() {
+        let grid = Grid::parse_from_str(TEST_INPUT).unwrap();
+        assert_eq!(grid.lowest_total_risk_tiled(1), Some(40));
+        assert_eq!(grid.lowest_total_risk_unscaled(), Some(40));
+        assert_eq!(grid.lowest_total_risk_tiled(5), Some(315));
+        assert_eq!(
+            grid.lowest_total_risk_tiled(5),
+            grid.lowest_total_risk(true)
+        );
+        assert_eq!(grid.lowest_total_risk_tiled(0), None);
+
+        let grid = Grid::parse_from_str("8\n").unwrap();
+        // Values increase by one (wrapping from 9 to 1) each step right or down
+        assert_eq!(grid.get_at(3, 0, 4), Some(2));
+        assert_eq!(grid.get_at(4, 0, 4), None);
+        assert_eq!(grid.get_at(1, 2, 4), Some(2));
+        assert_eq!(grid.lowest_total_risk_tiled(4), Some(9 + 1 + 2 + 3 + 4 + 5));
+    }
+
+    #[test]
+    fn test_lowest_cost_path_astar() {
+        let grid = Grid::parse_from_str(TEST_INPUT).unwrap();
+        assert_eq!(grid.lowest_total_risk_astar(false), Some(40));
+        assert_eq!(grid.lowest_total_risk_astar(true), Some(315));
+
+        for input in ["1\n", "19\n11\n", "1911\n1191\n9991\n", "116\n138\n213\n"] {
+            let grid = Grid::parse_from_str(input).unwrap();
+            for tiled in [false, true] {
+                assert_eq!(
+                    grid.lowest_total_risk_astar(tiled),
+                    grid.lowest_total_risk(tiled)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_lowest_total_risk_with_path() {
+        let grid = Grid::parse_from_str(TEST_INPUT).unwrap();
+        for tiled in [false, true] {
+            let (risk, path) = grid.lowest_total_risk_with_path(tiled).unwrap();
+            let (width, height) = grid.dimensions(Grid::tile_count(tiled));
+            assert_eq!(path[0], (0, 0));
+            assert_eq!(path[path.len() - 1], (width - 1, height - 1));
+
+            // Consecutive points are adjacent, and the path's risk (excluding
+            // the start) matches
+            for pair in path.windows(2) {
+                let (dx, dy) = (pair[1].0 - pair[0].0, pair[1].1 - pair[0].1);
+                assert_eq!(dx.abs() + dy.abs(), 1);
+            }
+            let path_risk: u32 = path[1..]
+                .iter()
+                .map(|&(x, y)| grid.get_at(x, y, Grid::tile_count(tiled)).unwrap() as u32)
+                .sum();
+            assert_eq!(path_risk, risk);
+        }
+
+        // The example's lowest risk path only moves right and down
+        let (_, path) = grid.lowest_total_risk_with_path(false).unwrap();
+        assert_eq!(path.len(), 10 + 10 - 1);
+
+        let grid = Grid::parse_from_str("1\n").unwrap();
+        assert_eq!(
+            grid.lowest_total_risk_with_path(false),
+            Some((0, vec![(0, 0)]))
+        );
+    }
+
+    #[test]
+    fn test_count_lowest_risk_paths() {
+        // Two different paths through the example have the lowest risk of 40
+        let grid = Grid::parse_from_str(TEST_INPUT).unwrap();
+        assert_eq!(grid.count_lowest_risk_paths(false), Some(2));
+
+        // Right then down, or down then right
+        let grid = Grid::parse_from_str("11\n11\n").unwrap();
+        assert_eq!(grid.count_lowest_risk_paths(false), Some(2));
+        let grid = Grid::parse_from_str("12\n11\n").unwrap();
+        assert_eq!(grid.count_lowest_risk_paths(false), Some(1));
+
+        // Every monotone path ties, of which there are 4 choose 2
+        let grid = Grid::parse_from_str("111\n111\n111\n").unwrap();
+        assert_eq!(grid.count_lowest_risk_paths(false), Some(6));
+
+        let grid = Grid::parse_from_str("1\n").unwrap();
+        assert_eq!(grid.count_lowest_risk_paths(false), Some(1));
+
+        // 78 choose 39 paths, far more than fit in a `usize`
+        let grid = Grid::from_fn(40, 40, |_, _| 1).unwrap();
+        assert_eq!(grid.count_lowest_risk_paths(false), Some(usize::MAX));
+    }
+
+    #[test]
+    fn test_risk_statistics() {
+        let grid = Grid::parse_from_str(TEST_INPUT).unwrap();
+        let stats = grid.risk_statistics();
+        assert!((stats.mean - 3.82).abs() < 1e-9);
+        assert!((stats.variance - 7.1676).abs() < 1e-9);
+        assert!(stats.skewness > 0.);
+        assert_eq!((stats.min, stats.max), (1, 9));
+
+        let grid = Grid::parse_from_str("55\n55\n").unwrap();
+        let stats = grid.risk_statistics();
+        assert_eq!(
+            stats,
+            GridRiskStats {
+                mean: 5.,
+                variance: 0.,
+                skewness: 0.,
+                min: 5,
+                max: 5,
+            }
+        );
+    }
+
+    #[test]
+    fn test_risk_entropy() {
+        let grid = Grid::parse_from_str(TEST_INPUT).unwrap();
+        let entropy = grid.risk_entropy();
+        assert!(entropy > 0. && entropy <= 9f64.log2());
+
+        let grid = Grid::parse_from_str("55\n55\n").unwrap();
+        assert_eq!(grid.risk_entropy(), 0.);
+
+        let grid = Grid::parse_from_str("12\n34\n").unwrap();
+        assert!((grid.risk_entropy() - 2.).abs() < 1e-9);
+    }
 }
@@ -1,6 +1,7 @@
 use std::{
     fmt::{self, Debug},
     fs,
+    iter::Peekable,
 };
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -21,7 +22,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
-enum Bit {
+pub enum Bit {
     L = 0,
     H,
 }
@@ -47,6 +48,20 @@ impl Bit {
         }
     }
 
+    /// Appends the lowest `width` bits of `num`, most significant first
+    fn push_num(bits: &mut Vec<Bit>, num: u64, width: u8) {
+        bits.extend((0..width).rev().map(|bit| Bit::from_u64(num, bit)));
+    }
+
+    #[inline]
+    fn from_u64(num: u64, bit: u8) -> Self {
+        if (num & (1 << bit)) != 0 {
+            H
+        } else {
+            L
+        }
+    }
+
     #[inline]
     fn as_num(bits: impl IntoIterator<Item = Bit>) -> u64 {
         let mut num: u64 = 0;
@@ -77,13 +92,13 @@ fn to_bits(input: &str) -> Option<Vec<Bit>> {
     Some(result)
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct Packet {
     pub version: u8,
     pub contents: PacketContents,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum PacketContents {
     Literal(u64),
     Operator {
@@ -92,7 +107,7 @@ pub enum PacketContents {
     },
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum OperatorType {
     Sum,
     Product,
@@ -119,26 +134,99 @@ impl OperatorType {
         }
     }
 
+    fn type_id(&self) -> u8 {
+        use OperatorType::*;
+        match self {
+            Sum => 0,
+            Product => 1,
+            Min => 2,
+            Max => 3,
+            GreaterThan => 5,
+            LessThan => 6,
+            Equal => 7,
+        }
+    }
+
     pub fn binary_op(&self) -> bool {
         use OperatorType::*;
         matches!(self, GreaterThan | LessThan | Equal)
     }
+
+    /// Applies the operator to the values of its subpackets. Sums and
+    /// products wrap around on overflow, unless `checked` (in which case
+    /// overflow gives `None`). Min and max of no values also give `None`
+    fn apply(&self, values: &[u64], checked: bool) -> Option<u64> {
+        use OperatorType::*;
+        let mut ops = values.iter().copied();
+        Some(match self {
+            Sum if checked => ops.try_fold(0, u64::checked_add)?,
+            Sum => ops.fold(0, u64::wrapping_add),
+            Product if checked => ops.try_fold(1, u64::checked_mul)?,
+            Product => ops.fold(1, u64::wrapping_mul),
+            Min => ops.min()?,
+            Max => ops.max()?,
+            GreaterThan => (values[0] > values[1]) as u64,
+            LessThan => (values[0] < values[1]) as u64,
+            Equal => (values[0] == values[1]) as u64,
+        })
+    }
 }
 
 impl Packet {
+    /// Maximum nesting of packets accepted by `parse`, to avoid overflowing
+    /// the stack on malformed input
+    pub const DEFAULT_MAX_DEPTH: usize = 64;
+
     pub fn parse(input: &str) -> Result<Self, &'static str> {
         let bits = to_bits(input).ok_or("invalid hex input")?;
         Self::parse_bits(bits)
     }
 
+    /// Like `parse`, but fails if packets are nested more than `max_depth`
+    /// deep (where a single packet has depth 1)
+    pub fn parse_with_limit(input: &str, max_depth: usize) -> Result<Self, &'static str> {
+        let bits = to_bits(input).ok_or("invalid hex input")?;
+        Self::parse_bits_with_limit(bits, max_depth)
+    }
+
+    /// Parses a string of `0`s and `1`s (rather than hex)
+    pub fn parse_binary_str(input: &str) -> Result<Self, &'static str> {
+        let input = input.trim_end();
+        if input.is_empty() {
+            return Err("empty input");
+        }
+        let bits = input
+            .chars()
+            .map(|c| match c {
+                '0' => Ok(L),
+                '1' => Ok(H),
+                _ => Err("invalid binary input"),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::parse_bits(bits)
+    }
+
     fn parse_bits(bits: impl IntoIterator<Item = Bit>) -> Result<Self, &'static str> {
-        let (_, packet) = Self::parse_helper(&mut bits.into_iter().enumerate().into())?;
+        Self::parse_bits_with_limit(bits, Self::DEFAULT_MAX_DEPTH)
+    }
+
+    fn parse_bits_with_limit(
+        bits: impl IntoIterator<Item = Bit>,
+        max_depth: usize,
+    ) -> Result<Self, &'static str> {
+        let bits = &mut bits.into_iter().enumerate().into();
+        let (_, packet) = Self::parse_helper(bits, max_depth)?;
         Ok(packet)
     }
 
     fn parse_helper(
         bits: &mut CountingIter<impl Iterator<Item = (usize, Bit)>>,
+        max_depth: usize,
     ) -> Result<(usize, Packet), &'static str> {
+        if max_depth == 0 {
+            return Err("max nesting depth exceeded");
+        }
+
         let start_idx = bits.processed;
 
         let version = Bit::as_num(bits.by_ref().map(|x| x.1).take(3)) as u8;
@@ -175,7 +263,8 @@ impl Packet {
                         let mut parsed_bits = 0;
                         let mut packets = Vec::new();
                         while parsed_bits < total_length as usize {
-                            let (parsed, packet) = Self::parse_helper(bits.by_ref())?;
+                            let (parsed, packet) =
+                                Self::parse_helper(bits.by_ref(), max_depth - 1)?;
                             parsed_bits += parsed;
                             packets.push(packet);
                         }
@@ -187,7 +276,7 @@ impl Packet {
                         let num_subpackets = Bit::as_num(bits.by_ref().map(|x| x.1).take(11));
                         let mut packets = Vec::with_capacity(num_subpackets as usize);
                         for _ in 0..num_subpackets {
-                            packets.push(Self::parse_helper(bits.by_ref())?.1);
+                            packets.push(Self::parse_helper(bits.by_ref(), max_depth - 1)?.1);
                         }
                         packets
                     }
@@ -218,6 +307,79 @@ impl Packet {
         Ok((end_idx - start_idx, packet))
     }
 
+    /// Serializes the packet (and its subpackets) following the BITS protocol.
+    /// Returns `None` if an operator has too many subpackets to count and
+    /// they are too long to fit in the 15-bit total length field
+    pub fn encode(&self) -> Option<Vec<Bit>> {
+        let mut bits = Vec::new();
+        self.encode_into(&mut bits)?;
+        Some(bits)
+    }
+
+    fn encode_into(&self, bits: &mut Vec<Bit>) -> Option<()> {
+        Bit::push_num(bits, self.version as u64, 3);
+
+        match &self.contents {
+            PacketContents::Literal(value) => {
+                Bit::push_num(bits, 4, 3);
+
+                let group_count = (64 - value.leading_zeros() as usize).div_ceil(4);
+                let group_count = group_count.max(1);
+                for group in (0..group_count).rev() {
+                    bits.push(if group == 0 { L } else { H });
+                    Bit::push_num(bits, value >> (group * 4), 4);
+                }
+            }
+            PacketContents::Operator { ty, subpackets } => {
+                Bit::push_num(bits, ty.type_id() as u64, 3);
+
+                const MAX_SUBPACKETS: usize = 1 << 11;
+                if subpackets.len() < MAX_SUBPACKETS {
+                    // Number of subpackets
+                    bits.push(H);
+                    Bit::push_num(bits, subpackets.len() as u64, 11);
+                    for packet in subpackets {
+                        packet.encode_into(bits)?;
+                    }
+                } else {
+                    // Total length in bits of the subpackets, which is only
+                    // known once they have been encoded
+                    bits.push(L);
+                    let length_idx = bits.len();
+                    Bit::push_num(bits, 0, 15);
+                    for packet in subpackets {
+                        packet.encode_into(bits)?;
+                    }
+
+                    let total_length = (bits.len() - length_idx - 15) as u64;
+                    if total_length >= 1 << 15 {
+                        return None;
+                    }
+                    let mut length_bits = Vec::with_capacity(15);
+                    Bit::push_num(&mut length_bits, total_length, 15);
+                    bits[length_idx..length_idx + 15].copy_from_slice(&length_bits);
+                }
+            }
+        }
+        Some(())
+    }
+
+    /// Serializes the packet as uppercase hexadecimal, padding the final
+    /// nibble with zeros. Returns `None` if the packet can't be encoded
+    pub fn to_hex(&self) -> Option<String> {
+        let hex = self
+            .encode()?
+            .chunks(4)
+            .map(|nibble| {
+                let padded = nibble.iter().copied().chain([L; 4]).take(4);
+                std::char::from_digit(Bit::as_num(padded) as u32, 16)
+                    .unwrap()
+                    .to_ascii_uppercase()
+            })
+            .collect();
+        Some(hex)
+    }
+
     pub fn version_sum(&self) -> u64 {
         let child_sum = match &self.contents {
             PacketContents::Literal(_) => 0,
@@ -228,28 +390,130 @@ impl Packet {
         self.version as u64 + child_sum
     }
 
+    pub fn version_max(&self) -> u8 {
+        let child_max = match &self.contents {
+            PacketContents::Literal(_) => 0,
+            PacketContents::Operator { subpackets, .. } => subpackets
+                .iter()
+                .map(|x| x.version_max())
+                .max()
+                .unwrap_or(0),
+        };
+        self.version.max(child_max)
+    }
+
+    /// Maximum nesting depth of the packet tree, where a literal has depth 0
+    pub fn depth(&self) -> usize {
+        match &self.contents {
+            PacketContents::Literal(_) => 0,
+            PacketContents::Operator { subpackets, .. } => {
+                1 + subpackets.iter().map(|x| x.depth()).max().unwrap_or(0)
+            }
+        }
+    }
+
+    /// All literal values in the tree, in depth-first preorder
+    pub fn literal_values(&self) -> Vec<u64> {
+        fn inner(packet: &Packet, values: &mut Vec<u64>) {
+            match &packet.contents {
+                PacketContents::Literal(value) => values.push(*value),
+                PacketContents::Operator { subpackets, .. } => {
+                    for subpacket in subpackets {
+                        inner(subpacket, values);
+                    }
+                }
+            }
+        }
+
+        let mut values = Vec::new();
+        inner(self, &mut values);
+        values
+    }
+
+    /// Evaluates the packet expression. Sums and products wrap around on
+    /// overflow, see `eval_checked` to detect this
     pub fn eval(&self) -> u64 {
         match &self.contents {
             PacketContents::Literal(value) => *value,
             PacketContents::Operator { ty, subpackets } => {
-                use OperatorType::*;
-                let ops = subpackets.iter().map(|x| x.eval());
-                match ty {
-                    Sum => ops.sum(),
-                    Product => ops.product(),
-                    Min => ops.min().unwrap(), // we guarantee at least one subpacket
-                    Max => ops.max().unwrap(),
-                    GreaterThan => (subpackets[0].eval() > subpackets[1].eval()) as u64,
-                    LessThan => (subpackets[0].eval() < subpackets[1].eval()) as u64,
-                    Equal => (subpackets[0].eval() == subpackets[1].eval()) as u64,
-                }
+                let values: Vec<_> = subpackets.iter().map(|x| x.eval()).collect();
+                // We guarantee at least one subpacket
+                ty.apply(&values, false).unwrap()
+            }
+        }
+    }
+
+    /// Describes the calculation `eval` would perform, without performing it.
+    /// Equivalent to the `Display` output
+    pub fn eval_symbolic(&self) -> String {
+        self.to_string()
+    }
+
+    /// Evaluates the packet expression, giving `None` if any sum or product
+    /// overflows
+    pub fn eval_checked(&self) -> Option<u64> {
+        match &self.contents {
+            PacketContents::Literal(value) => Some(*value),
+            PacketContents::Operator { ty, subpackets } => {
+                let values = subpackets
+                    .iter()
+                    .map(|x| x.eval_checked())
+                    .collect::<Option<Vec<_>>>()?;
+                ty.apply(&values, true)
+            }
+        }
+    }
+
+    /// Like `eval`, but returns `None` if evaluating would require recursing
+    /// deeper than `max_depth` (operators at depth `max_depth` are not
+    /// evaluated)
+    pub fn eval_depth_limited(&self, max_depth: usize) -> Option<u64> {
+        match &self.contents {
+            PacketContents::Literal(value) => Some(*value),
+            PacketContents::Operator { ty, subpackets } => {
+                let max_depth = max_depth.checked_sub(1)?;
+                let values = subpackets
+                    .iter()
+                    .map(|x| x.eval_depth_limited(max_depth))
+                    .collect::<Option<Vec<_>>>()?;
+                ty.apply(&values, false)
             }
         }
     }
 }
 
-pub struct CountingIter<I> {
-    iter: I,
+/// Displays the packet as a fully parenthesised expression
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (ty, subpackets) = match &self.contents {
+            PacketContents::Literal(value) => return write!(f, "{}", value),
+            PacketContents::Operator { ty, subpackets } => (ty, subpackets),
+        };
+
+        use OperatorType::*;
+        let (prefix, separator) = match ty {
+            Sum => ("(", " + "),
+            Product => ("(", " * "),
+            Min => ("min(", ", "),
+            Max => ("max(", ", "),
+            GreaterThan => ("(", " > "),
+            LessThan => ("(", " < "),
+            Equal => ("(", " == "),
+        };
+
+        write!(f, "{}", prefix)?;
+        for (i, subpacket) in subpackets.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{}", separator)?;
+            }
+            write!(f, "{}", subpacket)?;
+        }
+        write!(f, ")")
+    }
+}
+
+pub struct CountingIter<I: Iterator> {
+    iter: Peekable<I>,
     processed: usize,
 }
 
@@ -264,14 +528,32 @@ impl<I: Iterator> Iterator for CountingIter<I> {
 
 impl<I: Iterator> From<I> for CountingIter<I> {
     fn from(iter: I) -> Self {
-        Self { iter, processed: 0 }
+        Self {
+            iter: iter.peekable(),
+            processed: 0,
+        }
     }
 }
 
-impl<I> CountingIter<I> {
+impl<I: Iterator> CountingIter<I> {
     pub fn processed(&self) -> usize {
         self.processed
     }
+
+    /// Looks at the next item without counting it as processed
+    pub fn peek(&mut self) -> Option<&I::Item> {
+        self.iter.peek()
+    }
+
+    /// Restarts the count from the current position, for measuring the
+    /// length of a sub-sequence
+    pub fn reset_count(&mut self) {
+        self.processed = 0;
+    }
+
+    pub fn remaining_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
 }
 
 #[cfg(test)]
@@ -286,11 +568,139 @@ mod tests {
 
     const EXAMPLE_BITS: &str = "11101110000000001101010000001100100000100011000001100000";
 
+    #[test]
+    fn test_counting_iter() {
+        let mut iter = CountingIter::from(0..5);
+        assert_eq!(iter.remaining_hint(), (5, Some(5)));
+        assert_eq!(iter.by_ref().take(3).collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(iter.processed(), 3);
+
+        iter.reset_count();
+        assert_eq!(iter.processed(), 0);
+        assert_eq!(iter.peek(), Some(&3));
+        assert_eq!(iter.processed(), 0);
+        assert_eq!(iter.remaining_hint(), (2, Some(2)));
+
+        assert_eq!(iter.next(), Some(3));
+        assert_eq!(iter.processed(), 1);
+        assert_eq!(iter.next(), Some(4));
+        assert_eq!(iter.processed(), 2);
+        assert_eq!(iter.peek(), None);
+        assert_eq!(iter.remaining_hint(), (0, Some(0)));
+    }
+
     #[test]
     fn test_parse_bits() {
-        let bits = EXAMPLE_BITS.chars().map(|c| if c == '1' { H } else { L });
-        let packet = Packet::parse_bits(bits).unwrap();
+        let packet = Packet::parse_binary_str(EXAMPLE_BITS).unwrap();
         assert_eq!(packet.version_sum(), 7 + 2 + 4 + 1);
+        assert_eq!(packet, Packet::parse("EE00D40C823060").unwrap());
+
+        assert_eq!(Packet::parse_binary_str(""), Err("empty input"));
+        assert_eq!(Packet::parse_binary_str("\n"), Err("empty input"));
+        assert_eq!(
+            Packet::parse_binary_str("1110111000000000110102"),
+            Err("invalid binary input")
+        );
+        assert_eq!(
+            Packet::parse_binary_str("EE00D40C823060"),
+            Err("invalid binary input")
+        );
+    }
+
+    #[test]
+    fn test_parse_with_limit() {
+        // Literal
+        assert!(Packet::parse_with_limit("D2FE28", 1).is_ok());
+        assert_eq!(
+            Packet::parse_with_limit("D2FE28", 0),
+            Err("max nesting depth exceeded")
+        );
+
+        // An operator holding two literals
+        assert_eq!(
+            Packet::parse_with_limit("38006F45291200", 1),
+            Err("max nesting depth exceeded")
+        );
+        assert!(Packet::parse_with_limit("38006F45291200", 2).is_ok());
+
+        // Four nested packets
+        let input = "8A004A801A8002F478";
+        assert_eq!(
+            Packet::parse_with_limit(input, 3),
+            Err("max nesting depth exceeded")
+        );
+        assert_eq!(Packet::parse_with_limit(input, 4), Packet::parse(input));
+
+        // A long chain of operators each holding one subpacket
+        let mut packet = Packet::parse("D2FE28").unwrap();
+        for _ in 0..100 {
+            packet = Packet {
+                version: 0,
+                contents: PacketContents::Operator {
+                    ty: OperatorType::Sum,
+                    subpackets: vec![packet],
+                },
+            };
+        }
+        assert!(Packet::parse_bits(packet.encode().unwrap()).is_err());
+        assert_eq!(
+            Packet::parse_bits_with_limit(packet.encode().unwrap(), 101),
+            Ok(packet)
+        );
+    }
+
+    #[test]
+    fn test_tree_accessors() {
+        let packet = Packet::parse("D2FE28").unwrap();
+        assert_eq!(packet.version_max(), 6);
+        assert_eq!(packet.depth(), 0);
+        assert_eq!(packet.literal_values(), [2021]);
+
+        let packet = Packet::parse_binary_str(EXAMPLE_BITS).unwrap();
+        assert_eq!(packet.version_max(), 7);
+        assert_eq!(packet.depth(), 1);
+        assert_eq!(packet.literal_values(), [1, 2, 3]);
+
+        // Operator (v4) containing operator (v1) containing operator (v5)
+        // containing a literal (v6)
+        let packet = Packet::parse("8A004A801A8002F478").unwrap();
+        assert_eq!(packet.version_max(), 6);
+        assert_eq!(packet.depth(), 3);
+        assert_eq!(packet.literal_values(), [15]);
+
+        // 1 + 3 == 2 * 2
+        let packet = Packet::parse("9C0141080250320F1802104A08").unwrap();
+        assert_eq!(packet.depth(), 2);
+        assert_eq!(packet.literal_values(), [1, 3, 2, 2]);
+    }
+
+    #[test]
+    fn test_display() {
+        let display = |input| Packet::parse(input).unwrap().to_string();
+        assert_eq!(display("D2FE28"), "2021");
+        assert_eq!(display("C200B40A82"), "(1 + 2)");
+        assert_eq!(display("04005AC33890"), "(6 * 9)");
+        assert_eq!(display("880086C3E88112"), "min(7, 8, 9)");
+        assert_eq!(display("CE00C43D881120"), "max(7, 8, 9)");
+        assert_eq!(display("D8005AC2A8F0"), "(5 < 15)");
+        assert_eq!(display("F600BC2D8F"), "(5 > 15)");
+        assert_eq!(display("9C005AC2F8F0"), "(5 == 15)");
+        assert_eq!(
+            display("9C0141080250320F1802104A08"),
+            "((1 + 3) == (2 * 2))"
+        );
+        assert_eq!(display("8A004A801A8002F478"), "min(min(min(15)))");
+    }
+
+    #[test]
+    fn test_eval_symbolic() {
+        let symbolic = |input| Packet::parse(input).unwrap().eval_symbolic();
+        assert_eq!(symbolic("D2FE28"), "2021");
+        assert_eq!(symbolic("C200B40A82"), "(1 + 2)");
+        assert_eq!(
+            symbolic("9C0141080250320F1802104A08"),
+            "((1 + 3) == (2 * 2))"
+        );
     }
 
     #[test]
@@ -312,4 +722,168 @@ mod tests {
         let result = Packet::parse("9C0141080250320F1802104A08").unwrap().eval();
         assert_eq!(result, 1);
     }
+
+    #[test]
+    fn test_eval_checked() {
+        let operator = |ty, subpackets| Packet {
+            version: 0,
+            contents: PacketContents::Operator { ty, subpackets },
+        };
+        let literal = |value| Packet {
+            version: 0,
+            contents: PacketContents::Literal(value),
+        };
+
+        let large = || literal(1 << 40);
+        let product = operator(OperatorType::Product, vec![large(), large(), large()]);
+        assert_eq!(product.eval_checked(), None);
+        assert_eq!(product.eval(), 0); // 2^120 wraps to 0
+
+        let sum = operator(OperatorType::Sum, vec![literal(u64::MAX), literal(2)]);
+        assert_eq!(sum.eval_checked(), None);
+        assert_eq!(sum.eval(), 1);
+
+        // Overflow in a subpacket is propagated, even through comparisons
+        let comparison = operator(OperatorType::GreaterThan, vec![sum, literal(0)]);
+        assert_eq!(comparison.eval_checked(), None);
+        assert_eq!(comparison.eval(), 1);
+
+        assert_eq!(operator(OperatorType::Min, vec![]).eval_checked(), None);
+        assert_eq!(operator(OperatorType::Max, vec![]).eval_checked(), None);
+
+        for input in [
+            "C200B40A82",
+            "04005AC33890",
+            "880086C3E88112",
+            "CE00C43D881120",
+            "D8005AC2A8F0",
+            "F600BC2D8F",
+            "9C005AC2F8F0",
+            "9C0141080250320F1802104A08",
+        ] {
+            let packet = Packet::parse(input).unwrap();
+            assert_eq!(packet.eval_checked(), Some(packet.eval()));
+        }
+    }
+
+    #[test]
+    fn test_eval_depth_limited() {
+        let packet = Packet::parse("D2FE28").unwrap();
+        assert_eq!(packet.eval_depth_limited(0), Some(2021));
+
+        let packet = Packet::parse("C200B40A82").unwrap();
+        assert_eq!(packet.eval_depth_limited(0), None);
+        assert_eq!(packet.eval_depth_limited(1), Some(3));
+
+        // Nested operators
+        let packet = Packet::parse("9C0141080250320F1802104A08").unwrap();
+        assert_eq!(packet.eval_depth_limited(1), None);
+        assert_eq!(packet.eval_depth_limited(2), Some(1));
+
+        // Wraps around on overflow, like `eval`
+        let large = || Packet {
+            version: 0,
+            contents: PacketContents::Literal(1 << 40),
+        };
+        let product = Packet {
+            version: 0,
+            contents: PacketContents::Operator {
+                ty: OperatorType::Product,
+                subpackets: vec![large(), large(), large()],
+            },
+        };
+        assert_eq!(product.eval_depth_limited(1), Some(0));
+
+        for input in [
+            "C200B40A82",
+            "04005AC33890",
+            "880086C3E88112",
+            "CE00C43D881120",
+            "D8005AC2A8F0",
+            "F600BC2D8F",
+            "9C005AC2F8F0",
+            "9C0141080250320F1802104A08",
+        ] {
+            let packet = Packet::parse(input).unwrap();
+            assert_eq!(packet.eval_depth_limited(10), Some(packet.eval()));
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        let packet = Packet::parse("D2FE28").unwrap();
+        let expected = "110100101111111000101";
+        let bits = expected.chars().map(|c| if c == '1' { H } else { L });
+        assert_eq!(packet.encode().unwrap(), bits.collect::<Vec<_>>());
+        assert_eq!(packet.to_hex().unwrap(), "D2FE28");
+
+        let packet = Packet {
+            version: 0,
+            contents: PacketContents::Literal(0),
+        };
+        assert_eq!(packet.encode().unwrap(), [L, L, L, H, L, L, L, L, L, L, L]);
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        for input in [
+            "D2FE28",
+            "38006F45291200",
+            "EE00D40C823060",
+            "8A004A801A8002F478",
+            "620080001611562C8802118E34",
+            "C0015000016115A2E0802F182340",
+            "A0016C880162017C3686B18A3D4780",
+            "C200B40A82",
+            "04005AC33890",
+            "880086C3E88112",
+            "CE00C43D881120",
+            "D8005AC2A8F0",
+            "F600BC2D8F",
+            "9C005AC2F8F0",
+            "9C0141080250320F1802104A08",
+        ] {
+            let packet = Packet::parse(input).unwrap();
+            assert_eq!(Packet::parse(&packet.to_hex().unwrap()).unwrap(), packet);
+            assert_eq!(
+                Packet::parse_bits(packet.encode().unwrap()).unwrap(),
+                packet
+            );
+        }
+
+        // Enough subpackets to need the total length encoding
+        let packet = Packet {
+            version: 5,
+            contents: PacketContents::Operator {
+                ty: OperatorType::Sum,
+                subpackets: (0..2048)
+                    .map(|i| Packet {
+                        version: 1,
+                        contents: PacketContents::Literal(i % 16),
+                    })
+                    .collect(),
+            },
+        };
+        assert_eq!(Packet::parse(&packet.to_hex().unwrap()).unwrap(), packet);
+
+        // Each of these literals takes 11 bits, so 2978 of them fit in the
+        // 15-bit length field and 2979 don't
+        let sum_of_zeros = |count| Packet {
+            version: 0,
+            contents: PacketContents::Operator {
+                ty: OperatorType::Sum,
+                subpackets: (0..count)
+                    .map(|_| Packet {
+                        version: 0,
+                        contents: PacketContents::Literal(0),
+                    })
+                    .collect(),
+            },
+        };
+        let packet = sum_of_zeros(2978);
+        assert_eq!(Packet::parse(&packet.to_hex().unwrap()).unwrap(), packet);
+        let packet = sum_of_zeros(2979);
+        assert_eq!(packet.encode(), None);
+        assert_eq!(packet.to_hex(), None);
+    }
 }
@@ -24,6 +24,25 @@ impl Vec2 {
     pub const fn new(x: i32, y: i32) -> Self {
         Self { x, y }
     }
+
+    pub fn dot(self, other: Vec2) -> i32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The z component of the cross product, treating both as 3D vectors
+    pub fn cross_z(self, other: Vec2) -> i32 {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// Replaces each component by its sign, as when applying drag
+    pub fn normalize_dir(self) -> Vec2 {
+        Self::new(self.x.signum(), self.y.signum())
+    }
+
+    pub fn magnitude_squared(self) -> i64 {
+        let (x, y) = (self.x as i64, self.y as i64);
+        x * x + y * y
+    }
 }
 
 impl ops::Add for Vec2 {
@@ -92,9 +111,78 @@ impl Target {
         let (lhs, rhs) = range.split_once("..")?;
         Some((lhs.parse().ok()?, rhs.parse().ok()?))
     }
+
+    /// Whether `pos` lies within the target (bounds are inclusive)
+    pub fn contains(&self, pos: Vec2) -> bool {
+        (self.xmin..=self.xmax).contains(&pos.x) && (self.ymin..=self.ymax).contains(&pos.y)
+    }
+
+    /// Number of integer positions within the target
+    pub fn area(&self) -> u64 {
+        let width = (self.xmax - self.xmin + 1) as u64;
+        let height = (self.ymax - self.ymin + 1) as u64;
+        width * height
+    }
+
+    /// A copy of the target with each boundary moved outwards by `margin`
+    pub fn expand_by(&self, margin: i32) -> Target {
+        Self::new(
+            (self.xmin - margin, self.xmax + margin),
+            (self.ymin - margin, self.ymax + margin),
+        )
+    }
+}
+
+/// A probe in flight, which can be stepped through its trajectory one tick at a
+/// time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Probe {
+    position: Vec2,
+    velocity: Vec2,
+}
+
+impl Probe {
+    pub fn new(initial_velocity: Vec2) -> Self {
+        Self {
+            position: Vec2::ZERO,
+            velocity: initial_velocity,
+        }
+    }
+
+    pub fn step(&mut self) {
+        self.position += self.velocity;
+        self.velocity -= Vec2::new(self.velocity.x.signum(), 1);
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    pub fn velocity(&self) -> Vec2 {
+        self.velocity
+    }
+
+    /// Returns the positions after each of the next `max_steps` steps
+    pub fn trajectory(&self, max_steps: usize) -> Vec<Vec2> {
+        let mut probe = self.clone();
+        (0..max_steps)
+            .map(|_| {
+                probe.step();
+                probe.position
+            })
+            .collect()
+    }
+
+    /// Returns `true` if the probe is in the target region after any of the
+    /// next `max_steps` steps
+    pub fn hits_target(&self, target: &Target, max_steps: usize) -> bool {
+        self.trajectory(max_steps)
+            .into_iter()
+            .any(|pos| target.contains(pos))
+    }
 }
 
-fn x_velocity_range(xmin: i32, xmax: i32) -> (i32, i32) {
+pub fn x_velocity_range(xmin: i32, xmax: i32) -> (i32, i32) {
     if xmin < 0 {
         if xmax < 0 {
             // xmin ... xmax ... 0
@@ -109,20 +197,14 @@ fn x_velocity_range(xmin: i32, xmax: i32) -> (i32, i32) {
     }
 }
 
-fn y_velocity_range(ymin: i32, ymax: i32) -> (i32, i32) {
+pub fn y_velocity_range(ymin: i32, ymax: i32) -> (i32, i32) {
     if ymin < 0 {
         if ymax < 0 {
-            // Below ymin -> jumps over immediately. Max value seems way harder
-            // after some thinking, and a dummy value of 1000 works for the
-            // given input
-            // - Binary search not guaranteed to work due to discrete nature of
-            //   the problem
-            // - If region includes parts of x = 0, there is NO max y (shoot
-            //   straight up with velocity of your choice). As long as the
-            //   region includes at least one triangular number, it will work
-            //   (-1, -3, -6, -10, ...)
-            // - It also depends on the x position in nontrivial ways
-            (ymin, 1000)
+            // Below ymin -> jumps over immediately.
+            // Shot upwards with velocity v, the probe returns to y = 0 with
+            // velocity -v - 1, so lands at y = -v - 1 on the next step. If
+            // that is below ymin, the probe jumps over the whole range
+            (ymin, -ymin - 1)
         } else {
             // Below ymin -> jumps over immediately.
             // Above ymax -> jumps over on way up and down.
@@ -136,6 +218,74 @@ fn y_velocity_range(ymin: i32, ymax: i32) -> (i32, i32) {
     }
 }
 
+/// The initial x velocities for which the probe is within `xmin..=xmax` after
+/// some step, found by simulating each candidate from `x_velocity_range`
+pub fn valid_x_velocities(xmin: i32, xmax: i32) -> HashSet<i32> {
+    let (start, end) = x_velocity_range(xmin, xmax);
+    (start..=end)
+        .filter(|&initial| {
+            let (mut pos, mut vel) = (0, initial);
+            loop {
+                pos += vel;
+                vel -= vel.signum();
+                if (xmin..=xmax).contains(&pos) {
+                    return true;
+                }
+                // Stopped short, or moved past the range (the probe never
+                // turns back)
+                if vel == 0 || (vel > 0 && pos > xmax) || (vel < 0 && pos < xmin) {
+                    return false;
+                }
+            }
+        })
+        .collect()
+}
+
+/// The initial y velocities for which the probe is within `ymin..=ymax` after
+/// some step. Candidates come from `y_velocity_range`, which is bounded above
+/// by `-ymin - 1` for targets below the start
+pub fn valid_y_velocities(ymin: i32, ymax: i32) -> HashSet<i32> {
+    let (start, end) = y_velocity_range(ymin, ymax);
+    (start..=end)
+        .filter(|&initial| {
+            let (mut pos, mut vel) = (0, initial);
+            loop {
+                pos += vel;
+                vel -= 1;
+                if (ymin..=ymax).contains(&pos) {
+                    return true;
+                }
+                if vel < 0 && pos < ymin {
+                    return false;
+                }
+            }
+        })
+        .collect()
+}
+
+/// Every initial velocity which puts the probe in the target after some step.
+/// Each component must be valid alone, but the pair must also reach the
+/// target on the same step
+pub fn exact_valid_velocities(target: &Target) -> HashSet<(i32, i32)> {
+    let ys = valid_y_velocities(target.ymin, target.ymax);
+    valid_x_velocities(target.xmin, target.xmax)
+        .into_iter()
+        .flat_map(|x| ys.iter().map(move |&y| (x, y)))
+        .filter(|&(x, y)| {
+            let mut probe = Probe::new(Vec2::new(x, y));
+            loop {
+                probe.step();
+                if target.contains(probe.position()) {
+                    return true;
+                }
+                if probe.velocity().y < 0 && probe.position().y < target.ymin {
+                    return false;
+                }
+            }
+        })
+        .collect()
+}
+
 fn min_vel(val: i32) -> i32 {
     // If the object has velocity v, it moves by v, then v - 1, and so on,
     // totalling v + (v - 1) + ... + 1, which equals v * (v + 1) / 2.
@@ -170,8 +320,49 @@ pub fn solve(target: &Target) -> Option<(i32, usize)> {
     Some((ymax?, success_velocities.len()))
 }
 
+/// Returns the velocity which reaches the target in the fewest steps, along
+/// with the number of steps taken
+pub fn fastest_trajectory(target: &Target) -> Option<(Vec2, usize)> {
+    let (xs, xf) = x_velocity_range(target.xmin, target.xmax);
+    let (ys, yf) = y_velocity_range(target.ymin, target.ymax);
+    let yrange = (target.ymin, target.ymax);
+
+    let mut fastest: Option<(Vec2, usize)> = None;
+    for x_vel in xs..=xf {
+        for x in target.xmin..=target.xmax {
+            for y_vel in ys..=yf {
+                let velocity = Vec2::new(x_vel, y_vel);
+                if let Some(steps) = trajectory_steps(x, velocity, yrange) {
+                    if fastest.map(|(_, fewest)| steps < fewest).unwrap_or(true) {
+                        fastest = Some((velocity, steps));
+                    }
+                }
+            }
+        }
+    }
+
+    fastest
+}
+
 fn simulate_throw(target_x: i32, initial_velocity: Vec2, yrange: (i32, i32)) -> Option<i32> {
+    simulate_throw_helper(target_x, initial_velocity, yrange).map(|(ymax, _)| ymax)
+}
+
+/// Returns the number of steps taken before the probe first enters the target
+/// region, or `None` if it misses
+fn trajectory_steps(target_x: i32, initial_velocity: Vec2, yrange: (i32, i32)) -> Option<usize> {
+    simulate_throw_helper(target_x, initial_velocity, yrange).map(|(_, steps)| steps)
+}
+
+/// Returns the maximum y reached and the number of steps taken to enter the
+/// target region
+fn simulate_throw_helper(
+    target_x: i32,
+    initial_velocity: Vec2,
+    yrange: (i32, i32),
+) -> Option<(i32, usize)> {
     let mut ymax = 0;
+    let mut steps = 0;
 
     let x_step = -target_x.signum();
 
@@ -189,11 +380,12 @@ fn simulate_throw(target_x: i32, initial_velocity: Vec2, yrange: (i32, i32)) ->
                 ymax += velocity.y * (velocity.y + 1) / 2;
             }
 
-            return Some(ymax);
+            return Some((ymax, steps));
         }
 
         pos += velocity;
         ymax = ymax.max(pos.y);
+        steps += 1;
 
         velocity.y -= 1;
         if velocity.x != 0 {
@@ -221,6 +413,53 @@ mod tests {
         assert_eq!(target, Target::new((20, 30), (-10, -5)));
     }
 
+    #[test]
+    fn test_vec2() {
+        let a = Vec2::new(3, -2);
+        let b = Vec2::new(4, 5);
+        assert_eq!(a.dot(b), 2);
+        assert_eq!(a.dot(Vec2::ZERO), 0);
+        assert_eq!(a.cross_z(b), 23);
+        assert_eq!(b.cross_z(a), -23);
+        assert_eq!(a.cross_z(a), 0);
+
+        assert_eq!(a.normalize_dir(), Vec2::new(1, -1));
+        assert_eq!(Vec2::new(0, 7).normalize_dir(), Vec2::new(0, 1));
+        assert_eq!(Vec2::ZERO.normalize_dir(), Vec2::ZERO);
+
+        assert_eq!(a.magnitude_squared(), 13);
+        assert_eq!(Vec2::ZERO.magnitude_squared(), 0);
+        let big = Vec2::new(i32::MIN, i32::MAX);
+        assert_eq!(
+            big.magnitude_squared(),
+            (1 << 62) + (i32::MAX as i64).pow(2)
+        );
+    }
+
+    #[test]
+    fn test_target_bounds() {
+        let target = Target::new((20, 30), (-10, -5));
+        for corner in [(20, -10), (20, -5), (30, -10), (30, -5)] {
+            assert!(target.contains(Vec2::new(corner.0, corner.1)));
+        }
+        for outside in [
+            (19, -11),
+            (19, -4),
+            (31, -11),
+            (31, -4),
+            (25, -11),
+            (19, -7),
+        ] {
+            assert!(!target.contains(Vec2::new(outside.0, outside.1)));
+        }
+
+        assert_eq!(target.area(), 66);
+        let expanded = target.expand_by(1);
+        assert_eq!(expanded, Target::new((19, 31), (-11, -4)));
+        assert_eq!(expanded.area(), 13 * 8);
+        assert!(expanded.contains(Vec2::new(19, -11)));
+    }
+
     #[test]
     fn test_x_velocity_range() {
         assert_eq!(x_velocity_range(5, 7), (1, 7));
@@ -230,10 +469,117 @@ mod tests {
         assert_eq!(x_velocity_range(-20, -11), (-20, -3));
     }
 
+    #[test]
+    fn test_valid_velocities() {
+        let xs = valid_x_velocities(20, 30);
+        assert!(xs.contains(&6) && xs.contains(&7) && xs.contains(&15));
+        // 16 to 19 jump from short of the target to past it
+        assert!((16..=19).all(|x| !xs.contains(&x)));
+        assert!((20..=30).all(|x| xs.contains(&x)));
+        assert!(!xs.contains(&5) && !xs.contains(&31));
+        assert_eq!(
+            valid_x_velocities(-30, -20),
+            xs.iter().map(|x| -x).collect()
+        );
+
+        let ys = valid_y_velocities(-10, -5);
+        assert!(ys.contains(&-10) && ys.contains(&9));
+        assert!(!ys.contains(&-11) && !ys.contains(&10));
+
+        let target = Target::new((20, 30), (-10, -5));
+        let velocities = exact_valid_velocities(&target);
+        assert_eq!(velocities.len(), 112);
+        assert!(velocities.contains(&(6, 9)));
+        assert!(velocities.contains(&(30, -10)));
+        assert!(!velocities.contains(&(17, -4)));
+        assert_eq!(Some(velocities.len()), solve(&target).map(|(_, n)| n));
+    }
+
+    #[test]
+    fn test_y_velocity_range() {
+        assert_eq!(y_velocity_range(-10, -5), (-10, 9));
+        assert_eq!(y_velocity_range(-1, -1), (-1, 0));
+        assert_eq!(y_velocity_range(-4, 3), (-4, 3));
+
+        // The maximum height comes from the fastest upwards shot
+        let (_, vmax) = y_velocity_range(-10, -5);
+        assert_eq!(vmax * (vmax + 1) / 2, 45);
+    }
+
     #[test]
     fn test_solve() {
         let target = Target::new((20, 30), (-10, -5));
         let result = solve(&target);
         assert_eq!(result, Some((45, 112)));
     }
+
+    #[test]
+    fn test_trajectory_steps() {
+        let yrange = (-10, -5);
+        assert_eq!(trajectory_steps(28, Vec2::new(7, 2), yrange), Some(7));
+        assert_eq!(trajectory_steps(21, Vec2::new(6, 3), yrange), Some(9));
+        assert_eq!(trajectory_steps(30, Vec2::new(30, -10), yrange), Some(1));
+        assert_eq!(trajectory_steps(30, Vec2::new(17, -4), yrange), None);
+
+        // Agrees with `simulate_throw` on whether the target is hit
+        for x_vel in 0..=30 {
+            for y_vel in -10..=10 {
+                let velocity = Vec2::new(x_vel, y_vel);
+                for x in 20..=30 {
+                    assert_eq!(
+                        trajectory_steps(x, velocity, yrange).is_some(),
+                        simulate_throw(x, velocity, yrange).is_some()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_probe() {
+        let mut probe = Probe::new(Vec2::new(2, 3));
+        probe.step();
+        assert_eq!(probe.position(), Vec2::new(2, 3));
+        assert_eq!(probe.velocity(), Vec2::new(1, 2));
+        probe.step();
+        probe.step();
+        assert_eq!(probe.position(), Vec2::new(3, 6));
+        assert_eq!(probe.velocity(), Vec2::new(0, 0));
+
+        let probe = Probe::new(Vec2::new(-2, 0));
+        assert_eq!(
+            probe.trajectory(3),
+            [Vec2::new(-2, 0), Vec2::new(-3, -1), Vec2::new(-3, -3)]
+        );
+        assert_eq!(probe.position(), Vec2::ZERO);
+
+        let target = Target::new((20, 30), (-10, -5));
+        assert!(Probe::new(Vec2::new(7, 2)).hits_target(&target, 10));
+        assert!(!Probe::new(Vec2::new(7, 2)).hits_target(&target, 6));
+        assert!(Probe::new(Vec2::new(6, 9)).hits_target(&target, 100));
+        assert!(!Probe::new(Vec2::new(17, -4)).hits_target(&target, 100));
+    }
+
+    #[test]
+    fn test_fastest_trajectory() {
+        // A horizontal target straddling y = 0 is reached fastest by a direct
+        // horizontal shot
+        let target = Target::new((5, 7), (-1, 1));
+        let (_, steps) = fastest_trajectory(&target).unwrap();
+        assert_eq!(steps, 1);
+        assert_eq!(trajectory_steps(7, Vec2::new(7, 0), (-1, 1)), Some(1));
+
+        let target = Target::new((20, 30), (-10, -5));
+        let (velocity, steps) = fastest_trajectory(&target).unwrap();
+        assert_eq!(steps, 1);
+        assert!((20..=30).contains(&velocity.x));
+        assert!((-10..=-5).contains(&velocity.y));
+
+        // The trajectory reaching the maximum height is much slower
+        let max_y_steps = (20..=30)
+            .filter_map(|x| trajectory_steps(x, Vec2::new(6, 9), (-10, -5)))
+            .min()
+            .unwrap();
+        assert!(steps < max_y_steps);
+    }
 }
@@ -1,4 +1,4 @@
-use std::{fmt::Display, fs, mem, ops::AddAssign};
+use std::{fmt::Display, fs, mem, ops::AddAssign, str::FromStr};
 
 mod parse;
 
@@ -11,13 +11,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mag = expr.magnitude();
     println!("Magnitude of sum of all numbers in input: {}", mag);
 
+    #[cfg(feature = "parallel")]
+    let max = max_magnitude_pair_parallel(&nums);
+    #[cfg(not(feature = "parallel"))]
     let max = max_magnitude_pair(&nums);
     println!("Maximum magnitude of pair of numbers in input: {}", max);
 
     Ok(())
 }
 
-pub fn sum_many(nums: &[parse::SnailfishNum]) -> Expression {
+pub fn sum_many<'a>(nums: impl IntoIterator<Item = &'a parse::SnailfishNum>) -> Expression {
     let mut simplifier = ExpressionSimplifier::default();
     let mut expr = Expression::default();
     for num in nums {
@@ -27,31 +30,68 @@ pub fn sum_many(nums: &[parse::SnailfishNum]) -> Expression {
     expr
 }
 
+/// Adds the numbers in order without simplifying along the way, or `None` if
+/// there are no numbers. Note that simplifying the result is not the same as
+/// `sum_many` unless no intermediate sum needs reducing
+pub fn sum_tree(
+    nums: impl IntoIterator<Item = parse::SnailfishNum>,
+) -> Option<parse::SnailfishNum> {
+    nums.into_iter().reduce(|acc, num| acc + num)
+}
+
+pub fn simplify_tree(num: parse::SnailfishNum) -> parse::SnailfishNum {
+    let mut expr = Expression::default();
+    expr.join(&num);
+    ExpressionSimplifier::default().simplify(expr).to_tree()
+}
+
 pub fn max_magnitude_pair(nums: &[parse::SnailfishNum]) -> u64 {
     let mut simplifier = ExpressionSimplifier::default();
-    let mut expr = Expression::default();
+    (0..nums.len())
+        .map(|i| max_magnitude_with(i, nums, &mut simplifier))
+        .max()
+        .unwrap_or(0)
+}
 
+/// The largest magnitude of `nums[i]` added to any of the other numbers
+fn max_magnitude_with(
+    i: usize,
+    nums: &[parse::SnailfishNum],
+    simplifier: &mut ExpressionSimplifier,
+) -> u64 {
+    let mut expr = Expression::default();
     let mut max_magnitude = 0;
+    for j in 0..nums.len() {
+        if i == j {
+            continue;
+        }
 
-    for i in 0..nums.len() {
-        for j in 0..nums.len() {
-            if i == j {
-                continue;
-            }
-
-            expr.join(&nums[i]);
-            expr.join(&nums[j]);
+        expr.join(&nums[i]);
+        expr.join(&nums[j]);
 
-            expr = simplifier.simplify(expr);
-            max_magnitude = max_magnitude.max(expr.magnitude());
+        expr = simplifier.simplify(expr);
+        max_magnitude = max_magnitude.max(expr.magnitude());
 
-            expr.clear();
-        }
+        expr.clear();
     }
-
     max_magnitude
 }
 
+/// Equivalent to `max_magnitude_pair`, but checks pairs across multiple
+/// threads
+#[cfg(feature = "parallel")]
+pub fn max_magnitude_pair_parallel(nums: &[parse::SnailfishNum]) -> u64 {
+    use rayon::prelude::*;
+
+    (0..nums.len())
+        .into_par_iter()
+        .map_init(ExpressionSimplifier::default, |simplifier, i| {
+            max_magnitude_with(i, nums, simplifier)
+        })
+        .max()
+        .unwrap_or(0)
+}
+
 #[derive(Default)]
 pub struct Expression {
     entries: Vec<Entry>,
@@ -92,7 +132,36 @@ impl Display for Expression {
     }
 }
 
+impl FromStr for Expression {
+    type Err = &'static str;
+
+    /// Parses a single snailfish number
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let nums = parse::parse(s)?;
+        let num = nums.first().ok_or("No snailfish number given")?;
+        let mut expr = Expression::default();
+        expr.join(num);
+        Ok(expr)
+    }
+}
+
 impl Expression {
+    /// Adds the two numbers and fully simplifies the result
+    pub fn add_simplified(lhs: &parse::SnailfishNum, rhs: &parse::SnailfishNum) -> Expression {
+        let mut expr = Expression::default();
+        expr.join(lhs);
+        expr.join(rhs);
+        ExpressionSimplifier::default().simplify(expr)
+    }
+
+    /// Equivalent to `sum_many`, but `None` if there are no numbers
+    pub fn sum_all_simplified(nums: &[parse::SnailfishNum]) -> Option<Expression> {
+        if nums.is_empty() {
+            return None;
+        }
+        Some(sum_many(nums))
+    }
+
     pub fn clear(&mut self) {
         self.entries.clear();
     }
@@ -140,6 +209,60 @@ impl Expression {
 
         inner(&self.entries).1
     }
+
+    /// Converts back to the tree representation. Panics if the expression is
+    /// empty
+    pub fn to_tree(&self) -> parse::SnailfishNum {
+        fn inner(entries: &[Entry]) -> (usize, parse::SnailfishNum) {
+            match entries[0] {
+                Entry::Begin => {
+                    let (next, lhs) = inner(&entries[1..]);
+                    let (next2, rhs) = inner(&entries[(1 + next)..]);
+                    // Skip the matching `Entry::End`
+                    (2 + next + next2, lhs + rhs)
+                }
+                Entry::End => unreachable!("Unbalanced expression"),
+                Entry::Num(n) => (1, parse::SnailfishNum::Num(n)),
+            }
+        }
+
+        inner(&self.entries).1
+    }
+
+    /// Number of regular numbers in the expression
+    pub fn leaf_count(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry, Entry::Num(_)))
+            .count()
+    }
+
+    /// Maximum number of pairs any regular number is nested inside
+    pub fn depth(&self) -> usize {
+        let mut nesting = 0;
+        let mut max_nesting = 0;
+        for entry in &self.entries {
+            match entry {
+                Entry::Begin => {
+                    nesting += 1;
+                    max_nesting = max_nesting.max(nesting);
+                }
+                Entry::End => nesting -= 1,
+                Entry::Num(_) => {}
+            }
+        }
+        max_nesting
+    }
+
+    /// Whether the expression can't be simplified any further, that is no pair
+    /// is nested inside four others and no regular number is 10 or greater
+    pub fn is_reduced(&self) -> bool {
+        self.depth() <= 4
+            && self
+                .entries
+                .iter()
+                .all(|entry| !matches!(entry, Entry::Num(n) if *n >= 10))
+    }
 }
 
 impl<'a> AddAssign<&'a parse::SnailfishNum> for Expression {
@@ -149,7 +272,7 @@ impl<'a> AddAssign<&'a parse::SnailfishNum> for Expression {
 }
 
 #[derive(Default)]
-struct ExpressionSimplifier {
+pub struct ExpressionSimplifier {
     expr: Vec<Entry>,
     buffer: Vec<Entry>,
 }
@@ -159,7 +282,7 @@ impl ExpressionSimplifier {
         self.expr = expr.entries;
 
         loop {
-            while self.iter_explode() {}
+            while self.iter_explode(usize::MAX) > 0 {}
             if !self.iter_split() {
                 break;
             }
@@ -171,10 +294,54 @@ impl ExpressionSimplifier {
         }
     }
 
-    fn iter_explode(&mut self) -> bool {
+    /// Like `simplify`, but stops after `max_steps` reductions (explosions or
+    /// splits). Returns the expression along with the number of reductions
+    /// applied
+    pub fn simplify_with_limit(
+        &mut self,
+        expr: Expression,
+        max_steps: usize,
+    ) -> (Expression, usize) {
+        self.expr = expr.entries;
+
+        let mut steps = 0;
+        while steps < max_steps {
+            let exploded = self.iter_explode(max_steps - steps);
+            if exploded > 0 {
+                steps += exploded;
+                continue;
+            }
+
+            if !self.iter_split() {
+                break;
+            }
+            steps += 1;
+        }
+
         self.buffer.clear();
+        let expr = Expression {
+            entries: mem::take(&mut self.expr),
+        };
+        (expr, steps)
+    }
 
-        let mut has_changed = false;
+    /// Performs a single reduction, an explosion if possible or otherwise a
+    /// split. Returns the expression and whether it changed
+    pub fn step(&mut self, expr: Expression) -> (Expression, bool) {
+        let (expr, steps) = self.simplify_with_limit(expr, 1);
+        (expr, steps == 1)
+    }
+
+    /// Like `simplify`, but also returns the number of reductions applied
+    pub fn count_steps(&mut self, expr: Expression) -> (Expression, usize) {
+        self.simplify_with_limit(expr, usize::MAX)
+    }
+
+    /// Performs up to `limit` explosions, returning the number performed
+    fn iter_explode(&mut self, limit: usize) -> usize {
+        self.buffer.clear();
+
+        let mut explosions = 0;
 
         let mut nesting = 0;
         let mut prev_num: Option<usize> = None;
@@ -200,7 +367,7 @@ impl ExpressionSimplifier {
 
                     next_inc = None;
 
-                    if nesting != 5 {
+                    if nesting != 5 || explosions == limit {
                         prev_num = Some(self.buffer.len());
                         self.buffer.push(Entry::Num(n));
                         continue;
@@ -208,7 +375,7 @@ impl ExpressionSimplifier {
 
                     if let Some(Entry::Num(next)) = self.expr.get(idx + 1) {
                         // Perform explode
-                        has_changed = true;
+                        explosions += 1;
 
                         if let Some(prev) = prev_num {
                             if let Entry::Num(prev) = &mut self.buffer[prev] {
@@ -233,7 +400,7 @@ impl ExpressionSimplifier {
         debug_assert_eq!(nesting, 0);
 
         mem::swap(&mut self.expr, &mut self.buffer);
-        has_changed
+        explosions
     }
 
     fn iter_split(&mut self) -> bool {
@@ -318,8 +485,48 @@ mod tests {
                 let mut expr = Expression::default();
                 expr.join(&parsed[0]);
                 assert_eq!(expr.to_string(), input);
+                assert_eq!(parsed[0].to_string(), input);
+
+                let reparsed: Expression = expr.to_string().parse().unwrap();
+                assert_eq!(reparsed.to_string(), input);
             }
         }
+
+        #[test]
+        fn test_structure() {
+            let expr: Expression = "7".parse().unwrap();
+            assert_eq!(expr.leaf_count(), 1);
+            assert_eq!(expr.depth(), 0);
+            assert!(expr.is_reduced());
+
+            let expr: Expression = "[[1,2],3]".parse().unwrap();
+            assert_eq!(expr.leaf_count(), 3);
+            assert_eq!(expr.depth(), 2);
+            assert!(expr.is_reduced());
+
+            let expr: Expression = "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]".parse().unwrap();
+            assert_eq!(expr.leaf_count(), 9);
+            assert_eq!(expr.depth(), 4);
+            assert!(expr.is_reduced());
+
+            let expr: Expression = "[[[[[9,8],1],2],3],4]".parse().unwrap();
+            assert_eq!(expr.depth(), 5);
+            assert!(!expr.is_reduced());
+
+            let expr: Expression = "[10,1]".parse().unwrap();
+            assert_eq!(expr.depth(), 1);
+            assert!(!expr.is_reduced());
+        }
+
+        #[test]
+        fn test_from_str() {
+            let expr: Expression = "[[1,2],3]".parse().unwrap();
+            assert_eq!(expr.entries.len(), 7);
+            assert_eq!(expr.to_string(), "[[1,2],3]");
+
+            assert!("".parse::<Expression>().is_err());
+            assert!("[1,".parse::<Expression>().is_err());
+        }
     }
 
     #[test]
@@ -348,11 +555,115 @@ mod tests {
             let mut expr = Expression::default();
             expr.join(&tree[0]);
 
+            assert!(!expr.is_reduced());
             let expr = simplifier.simplify(expr);
+            assert!(expr.is_reduced());
             expr.to_string()
         }
     }
 
+    #[test]
+    fn test_simplify_with_limit() {
+        let mut simplifier = ExpressionSimplifier::default();
+
+        for input in [
+            "[[[[[9,8],1],2],3],4]",
+            "[7,[6,[5,[4,[3,2]]]]]",
+            "[[6,[5,[4,[3,2]]]],1]",
+            "[[3,[2,[1,[7,3]]]],[6,[5,[4,[3,2]]]]]",
+            "[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]",
+        ] {
+            let (limited, _) = limited(&mut simplifier, input, usize::MAX);
+            let expected = simplifier.simplify(to_expr(input)).to_string();
+            assert_eq!(limited, expected);
+        }
+
+        let input = "[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]";
+        let result = limited(&mut simplifier, input, usize::MAX);
+        assert_eq!(result, ("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]".to_string(), 5));
+
+        // One reduction at a time
+        let result = limited(&mut simplifier, input, 0);
+        assert_eq!(result, (input.to_string(), 0));
+        let result = limited(&mut simplifier, input, 1);
+        assert_eq!(result, ("[[[[0,7],4],[7,[[8,4],9]]],[1,1]]".to_string(), 1));
+        let result = limited(&mut simplifier, input, 2);
+        assert_eq!(result, ("[[[[0,7],4],[15,[0,13]]],[1,1]]".to_string(), 2));
+        let result = limited(&mut simplifier, input, 3);
+        assert_eq!(
+            result,
+            ("[[[[0,7],4],[[7,8],[0,13]]],[1,1]]".to_string(), 3)
+        );
+        let result = limited(&mut simplifier, input, 4);
+        assert_eq!(
+            result,
+            ("[[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]".to_string(), 4)
+        );
+
+        // Already simplified
+        let result = limited(&mut simplifier, "[[1,2],3]", 10);
+        assert_eq!(result, ("[[1,2],3]".to_string(), 0));
+
+        fn to_expr(input: &str) -> Expression {
+            let mut expr = Expression::default();
+            expr.join(&parse::parse(input).unwrap()[0]);
+            expr
+        }
+
+        fn limited(
+            simplifier: &mut ExpressionSimplifier,
+            input: &str,
+            max_steps: usize,
+        ) -> (String, usize) {
+            let (expr, steps) = simplifier.simplify_with_limit(to_expr(input), max_steps);
+            (expr.to_string(), steps)
+        }
+    }
+
+    #[test]
+    fn test_step() {
+        let mut simplifier = ExpressionSimplifier::default();
+
+        let expr: Expression = "[[[[[9,8],1],2],3],4]".parse().unwrap();
+        let (expr, changed) = simplifier.step(expr);
+        assert!(changed);
+        assert_eq!(expr.to_string(), "[[[[0,9],2],3],4]");
+        let (expr, changed) = simplifier.step(expr);
+        assert!(!changed);
+        assert_eq!(expr.to_string(), "[[[[0,9],2],3],4]");
+
+        let expr: Expression = "[[[[[9,8],1],2],3],4]".parse().unwrap();
+        let (_, steps) = simplifier.count_steps(expr);
+        assert_eq!(steps, 1);
+
+        // Explode, explode, split, split, explode
+        let input = "[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]";
+        let mut expr: Expression = input.parse().unwrap();
+        let mut states = Vec::new();
+        loop {
+            let (next, changed) = simplifier.step(expr);
+            expr = next;
+            if !changed {
+                break;
+            }
+            states.push(expr.to_string());
+        }
+        assert_eq!(
+            states,
+            [
+                "[[[[0,7],4],[7,[[8,4],9]]],[1,1]]",
+                "[[[[0,7],4],[15,[0,13]]],[1,1]]",
+                "[[[[0,7],4],[[7,8],[0,13]]],[1,1]]",
+                "[[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]",
+                "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]",
+            ]
+        );
+
+        let (expr, steps) = simplifier.count_steps(input.parse().unwrap());
+        assert_eq!(steps, 5);
+        assert_eq!(expr.to_string(), states[4]);
+    }
+
     #[test]
     fn test_sum_all() {
         let sum = sum_from_str("[1,1]\n[2,2]\n[3,3]\n[4,4]");
@@ -393,7 +704,52 @@ mod tests {
         assert_eq!(sum_from_str(example), expected);
 
         fn sum_from_str(input: &str) -> String {
-            sum_many(&parse::parse(input).unwrap()).to_string()
+            let sum = sum_many(&parse::parse(input).unwrap()).to_string();
+
+            let nums = parse::parse_iter(input)
+                .map(Result::unwrap)
+                .collect::<Vec<_>>();
+            assert_eq!(sum_many(nums.iter()).to_string(), sum);
+            sum
+        }
+    }
+
+    #[test]
+    fn test_add_simplified() {
+        let nums = parse::parse("[1,2]\n[[3,4],5]").unwrap();
+        let sum = Expression::add_simplified(&nums[0], &nums[1]);
+        assert_eq!(sum.to_string(), "[[1,2],[[3,4],5]]");
+
+        let nums = parse::parse("[[[[4,3],4],4],[7,[[8,4],9]]]\n[1,1]").unwrap();
+        let sum = Expression::add_simplified(&nums[0], &nums[1]);
+        assert_eq!(sum.to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+
+        assert!(Expression::sum_all_simplified(&[]).is_none());
+        let nums = parse::parse("[1,1]\n[2,2]\n[3,3]\n[4,4]\n[5,5]").unwrap();
+        let sum = Expression::sum_all_simplified(&nums).unwrap();
+        assert_eq!(sum.to_string(), "[[[[3,0],[5,3]],[4,4]],[5,5]]");
+        assert_eq!(sum.to_string(), sum_many(&nums).to_string());
+    }
+
+    #[test]
+    fn test_sum_tree() {
+        assert_eq!(sum_tree(vec![]), None);
+
+        let input = "[1,1]\n[2,2]\n[3,3]\n[4,4]";
+        let nums = parse::parse(input).unwrap();
+        let sum = sum_tree(nums.clone()).unwrap();
+        assert_eq!(sum.to_string(), "[[[[1,1],[2,2]],[3,3]],[4,4]]");
+        assert_eq!(simplify_tree(sum).to_string(), sum_many(&nums).to_string());
+
+        let input = "[[[[4,3],4],4],[7,[[8,4],9]]]\n[1,1]";
+        let nums = parse::parse(input).unwrap();
+        let sum = simplify_tree(sum_tree(nums.clone()).unwrap());
+        assert_eq!(sum.to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+        assert_eq!(sum.to_string(), sum_many(&nums).to_string());
+
+        for input in ["7", "[[1,2],3]", "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]"] {
+            let expr: Expression = input.parse().unwrap();
+            assert_eq!(expr.to_tree().to_string(), input);
         }
     }
 
@@ -426,9 +782,7 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_max_magnitude_pair() {
-        const EXAMPLE: &str = "\
+    const HOMEWORK_EXAMPLE: &str = "\
 [[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]
 [[[5,[2,8]],4],[5,[[9,9],0]]]
 [6,[[[6,2],[5,6]],[[7,6],[4,7]]]]
@@ -440,8 +794,46 @@ mod tests {
 [[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
 [[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]";
 
-        let nums = parse::parse(EXAMPLE).unwrap();
+    #[test]
+    fn test_max_magnitude_pair() {
+        let nums = parse::parse(HOMEWORK_EXAMPLE).unwrap();
         let max = max_magnitude_pair(&nums);
         assert_eq!(max, 3993);
+
+        #[cfg(feature = "parallel")]
+        {
+            assert_eq!(max_magnitude_pair_parallel(&nums), 3993);
+            assert_eq!(max_magnitude_pair_parallel(&nums[..1]), 0);
+            assert_eq!(max_magnitude_pair_parallel(&[]), 0);
+        }
+    }
+
+    /// Run with `cargo test --release --features parallel --bin day18 --
+    /// --ignored --nocapture`
+    #[cfg(feature = "parallel")]
+    #[test]
+    #[ignore]
+    fn bench_max_magnitude_pair_parallel() {
+        use std::time::Instant;
+
+        for copies in [1, 5, 20] {
+            let nums = parse::parse(&vec![HOMEWORK_EXAMPLE; copies].join("\n")).unwrap();
+
+            let start = Instant::now();
+            let sequential = max_magnitude_pair(&nums);
+            let sequential_time = start.elapsed();
+
+            let start = Instant::now();
+            let parallel = max_magnitude_pair_parallel(&nums);
+            let parallel_time = start.elapsed();
+
+            assert_eq!(sequential, parallel);
+            println!(
+                "{} numbers: sequential {:?}, parallel {:?}",
+                nums.len(),
+                sequential_time,
+                parallel_time
+            );
+        }
     }
 }
@@ -4,19 +4,48 @@ pub enum SnailfishNum {
     Num(u32),
 }
 
+impl std::fmt::Display for SnailfishNum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnailfishNum::Pair(children) => write!(f, "[{},{}]", children[0], children[1]),
+            SnailfishNum::Num(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+/// Pairs the two numbers, without simplifying the result
+impl std::ops::Add for SnailfishNum {
+    type Output = SnailfishNum;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        SnailfishNum::Pair(Box::new([self, rhs]))
+    }
+}
+
+/// Parses one number per line, requiring at least one number
 pub fn parse(input: &str) -> Result<Vec<SnailfishNum>, &'static str> {
-    let (_, nums) = snailfish_nums(input).map_err(|_| "Failed to parse")?;
+    let nums = parse_iter(input).collect::<Result<Vec<_>, _>>()?;
+    if nums.is_empty() {
+        return Err("No snailfish numbers");
+    }
     Ok(nums)
 }
 
+/// Lazily parses one number per line
+pub fn parse_iter(input: &str) -> impl Iterator<Item = Result<SnailfishNum, &'static str>> + '_ {
+    input
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .filter(|line| !line.is_empty())
+        .map(|line| match snailfish_num(line) {
+            Ok(("", num)) => Ok(num),
+            _ => Err("Failed to parse"),
+        })
+}
+
 type ParseInput<'a> = &'a str;
 type ParseResult<'a, O> = nom::IResult<ParseInput<'a>, O, nom::error::VerboseError<ParseInput<'a>>>;
 
-fn snailfish_nums(i: ParseInput<'_>) -> ParseResult<'_, Vec<SnailfishNum>> {
-    use nom::{character::complete::line_ending, multi::separated_list1};
-    separated_list1(line_ending, snailfish_num)(i)
-}
-
 fn snailfish_num(i: ParseInput<'_>) -> ParseResult<'_, SnailfishNum> {
     use nom::{branch::alt, character::complete::char, combinator::map, sequence::tuple};
     alt((
@@ -71,5 +100,55 @@ mod tests {
                 Pair(Box::new([Num(3), Num(4)]))
             ]
         );
+
+        assert_eq!(parse(""), Err("No snailfish numbers"));
+        assert_eq!(parse("\n\r\n"), Err("No snailfish numbers"));
+    }
+
+    #[test]
+    fn test_parse_iter() {
+        use super::{parse_iter, SnailfishNum::*};
+
+        let mut nums = parse_iter("[1,2]\n[[3,4],5]\n");
+        assert_eq!(nums.next(), Some(Ok(Pair(Box::new([Num(1), Num(2)])))));
+        assert_eq!(
+            nums.next(),
+            Some(Ok(Pair(Box::new([
+                Pair(Box::new([Num(3), Num(4)])),
+                Num(5)
+            ]))))
+        );
+        assert_eq!(nums.next(), None);
+
+        let input = "[1,2]\r\n[3,4]";
+        let nums: Vec<_> = parse_iter(input).collect::<Result<_, _>>().unwrap();
+        assert_eq!(Ok(nums), super::parse("[1,2]\n[3,4]"));
+
+        let mut nums = parse_iter("[1,2]\n[3,\n[5,6]");
+        assert!(nums.next().unwrap().is_ok());
+        assert!(nums.next().unwrap().is_err());
+        assert!(nums.next().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_add() {
+        use super::SnailfishNum::*;
+
+        assert_eq!(Num(1) + Num(2), Pair(Box::new([Num(1), Num(2)])));
+        let sum = super::parse("[1,2]").unwrap().remove(0) + Num(3);
+        assert_eq!(sum.to_string(), "[[1,2],3]");
+    }
+
+    #[test]
+    fn test_display() {
+        for input in [
+            "7",
+            "[1,2]",
+            "[[1,9],[8,5]]",
+            "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]",
+        ] {
+            let result = super::parse(input).unwrap();
+            assert_eq!(result[0].to_string(), input);
+        }
     }
 }
@@ -6,14 +6,14 @@ use std::{
 };
 
 #[derive(PartialEq, Eq, Debug, Clone)]
-enum Action {
+pub enum Action {
     Forward(i64),
     Down(i64),
     Up(i64),
 }
 
-#[derive(Debug)]
-struct Position {
+#[derive(Debug, PartialEq, Eq)]
+pub struct Position {
     horizontal: i64,
     depth: i64,
     aim: i64,
@@ -46,6 +46,18 @@ impl Position {
             Action::Up(amount) => self.aim -= amount,
         }
     }
+
+    /// Horizontal position multiplied by depth
+    pub fn product(&self) -> i64 {
+        self.horizontal * self.depth
+    }
+
+    pub fn to_csv(&self) -> String {
+        format!(
+            "horizontal,depth,aim\n{},{},{}",
+            self.horizontal, self.depth, self.aim
+        )
+    }
 }
 
 impl Display for Position {
@@ -53,16 +65,38 @@ impl Display for Position {
         writeln!(f, "Position:")?;
         writeln!(f, "  Horizontal Position: {}", self.horizontal)?;
         writeln!(f, "  Depth: {}", self.depth)?;
-        write!(f, "  Product of above: {}", self.horizontal * self.depth)?;
+        write!(f, "  Product of above: {}", self.product())?;
         Ok(())
     }
 }
 
+/// Final position after applying all actions, using the aim
+pub fn apply_actions(actions: &[Action]) -> Position {
+    let mut position = Position::new();
+    for action in actions {
+        position.apply_action(action);
+    }
+    position
+}
+
+/// Final position after applying all actions, ignoring the aim
+pub fn apply_actions_naive(actions: &[Action]) -> Position {
+    let mut position = Position::new();
+    for action in actions {
+        position.apply_action_naive(action);
+    }
+    position
+}
+
 fn parse_line(line: &str) -> Result<Action, &'static str> {
-    use Action::*;
     let (action, count) = line
         .split_once(' ')
         .ok_or("Expected space delimiter on line")?;
+    parse_action(action, count)
+}
+
+fn parse_action(action: &str, count: &str) -> Result<Action, &'static str> {
+    use Action::*;
     let count = count.parse().map_err(|_| "Invalid count")?;
     Ok(match action {
         "forward" => Forward(count),
@@ -72,14 +106,49 @@ fn parse_line(line: &str) -> Result<Action, &'static str> {
     })
 }
 
-fn parse_input(input: impl BufRead) -> Result<Vec<Action>, Box<dyn Error>> {
-    let actions = input
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?
-        .iter()
-        .map(|l| parse_line(l))
-        .collect::<Result<Vec<_>, _>>();
-    Ok(actions?)
+fn parse_input(mut input: impl BufRead) -> Result<Vec<Action>, Box<dyn Error>> {
+    let mut contents = String::new();
+    input.read_to_string(&mut contents)?;
+    Ok(parse_from_str(&contents)?)
+}
+
+pub fn parse_from_str(input: &str) -> Result<Vec<Action>, &'static str> {
+    input.lines().map(parse_line).collect()
+}
+
+type ParseError = Box<dyn Error>;
+
+const CSV_HEADER: &str = "action,magnitude";
+
+/// Parses actions from CSV with columns `action,magnitude` (e.g. `forward,5`).
+/// A leading header row is optional
+pub fn parse_actions_from_csv(input: impl BufRead) -> Result<Vec<Action>, ParseError> {
+    let mut actions = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        if i == 0 && line == CSV_HEADER {
+            continue;
+        }
+
+        let (action, count) = line
+            .split_once(',')
+            .ok_or("Expected comma delimiter on line")?;
+        actions.push(parse_action(action, count)?);
+    }
+    Ok(actions)
+}
+
+pub fn actions_to_csv(actions: &[Action]) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    for action in actions {
+        let (name, count) = match action {
+            Action::Forward(count) => ("forward", count),
+            Action::Down(count) => ("down", count),
+            Action::Up(count) => ("up", count),
+        };
+        csv += &format!("\n{},{}", name, count);
+    }
+    csv
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -89,18 +158,10 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let actions = parse_input(reader)?;
 
-    let mut position = Position::new();
-    for action in actions.iter() {
-        position.apply_action_naive(action);
-    }
-
+    let position = apply_actions_naive(&actions);
     println!("{}", position);
 
-    let mut position = Position::new();
-    for action in actions.iter() {
-        position.apply_action(action);
-    }
-
+    let position = apply_actions(&actions);
     println!("\n{}", position);
 
     Ok(())
@@ -170,4 +231,89 @@ mod tests {
         assert_eq!(position.horizontal, 8);
         assert_eq!(position.aim, 4);
     }
+
+    #[test]
+    fn test_apply_actions() {
+        let input = "\
+forward 5
+down 5
+forward 8
+up 3
+down 8
+forward 2
+";
+        let actions = parse_from_str(input).unwrap();
+        assert_eq!(actions.len(), 6);
+
+        let position = apply_actions_naive(&actions);
+        assert_eq!((position.horizontal, position.depth), (15, 10));
+        assert_eq!(position.product(), 150);
+
+        let position = apply_actions(&actions);
+        assert_eq!((position.horizontal, position.depth), (15, 60));
+        assert_eq!(position.product(), 900);
+
+        assert_eq!(apply_actions(&[]), Position::new());
+        assert_eq!(
+            parse_from_str("forward 5\nup"),
+            Err("Expected space delimiter on line")
+        );
+    }
+
+    mod csv {
+        use super::*;
+
+        use std::io;
+
+        #[test]
+        fn position_to_csv() {
+            let mut position = Position::new();
+            assert_eq!(position.to_csv(), "horizontal,depth,aim\n0,0,0");
+            position.apply_action(&Action::Down(7));
+            position.apply_action(&Action::Forward(2));
+            assert_eq!(position.to_csv(), "horizontal,depth,aim\n2,14,7");
+        }
+
+        #[test]
+        fn parse_actions() {
+            let input = io::Cursor::new("forward,5\ndown,3\nup,1");
+            let result = parse_actions_from_csv(input).expect("Should succeed");
+            assert_eq!(result, [Action::Forward(5), Action::Down(3), Action::Up(1)]);
+
+            let input = io::Cursor::new("action,magnitude\nforward,5");
+            let result = parse_actions_from_csv(input).expect("Should succeed");
+            assert_eq!(result, [Action::Forward(5)]);
+
+            let input = io::Cursor::new("forward 5");
+            let result = parse_actions_from_csv(input);
+            assert_eq!(
+                result.expect_err("Should fail").to_string(),
+                "Expected comma delimiter on line"
+            );
+        }
+
+        #[test]
+        fn round_trip() {
+            let actions = [
+                Action::Forward(5),
+                Action::Down(5),
+                Action::Forward(8),
+                Action::Up(3),
+                Action::Down(8),
+                Action::Forward(2),
+            ];
+            let csv = actions_to_csv(&actions);
+            let parsed = parse_actions_from_csv(io::Cursor::new(csv)).expect("Should succeed");
+            assert_eq!(parsed, actions);
+
+            let mut expected = Position::new();
+            let mut actual = Position::new();
+            for (a, b) in actions.iter().zip(parsed.iter()) {
+                expected.apply_action(a);
+                actual.apply_action(b);
+            }
+            assert_eq!(actual, expected);
+            assert_eq!(actual.to_csv(), "horizontal,depth,aim\n15,60,10");
+        }
+    }
 }
@@ -1,21 +1,38 @@
 use std::{
+    cmp::Ordering,
     fs::File,
     io::{BufRead, BufReader},
 };
 
-const BIT_COUNT: u8 = 12;
-
+/// Ties go to `1`
 fn most_common_bit(bit: u8, nums: &[u16]) -> u16 {
+    most_common_bit_with_tiebreak(bit, nums, 1)
+}
+
+/// Ties go to `0`
+fn least_common_bit(bit: u8, nums: &[u16]) -> u16 {
+    least_common_bit_with_tiebreak(bit, nums, 0)
+}
+
+/// Returns `tiebreak` if zeros and ones appear equally often. Panics if
+/// `tiebreak` is not a bit (`0` or `1`)
+fn most_common_bit_with_tiebreak(bit: u8, nums: &[u16], tiebreak: u16) -> u16 {
+    assert!(tiebreak <= 1, "tiebreak must be 0 or 1, got {}", tiebreak);
+
     let zero_count = nums.iter().filter(|num| (*num >> bit) & 1 == 0).count();
-    if zero_count > nums.len() / 2 {
-        0
-    } else {
-        1
+    let one_count = nums.len() - zero_count;
+    match zero_count.cmp(&one_count) {
+        Ordering::Greater => 0,
+        Ordering::Less => 1,
+        Ordering::Equal => tiebreak,
     }
 }
 
-fn least_common_bit(bit: u8, nums: &[u16]) -> u16 {
-    1 - most_common_bit(bit, nums)
+/// Returns `tiebreak` if zeros and ones appear equally often. Panics if
+/// `tiebreak` is not a bit (`0` or `1`)
+fn least_common_bit_with_tiebreak(bit: u8, nums: &[u16], tiebreak: u16) -> u16 {
+    assert!(tiebreak <= 1, "tiebreak must be 0 or 1, got {}", tiebreak);
+    1 - most_common_bit_with_tiebreak(bit, nums, 1 - tiebreak)
 }
 
 fn calculate_gamma(bit_count: u8, nums: &[u16]) -> u16 {
@@ -25,7 +42,8 @@ fn calculate_gamma(bit_count: u8, nums: &[u16]) -> u16 {
 }
 
 fn calculate_epsilon(bit_count: u8, gamma: u16) -> u16 {
-    !gamma & ((1 << bit_count) - 1)
+    // Shifting a full mask down avoids overflowing when `bit_count` is 16
+    !gamma & u16::MAX.checked_shr(16 - bit_count as u32).unwrap_or(0)
 }
 
 fn calculate_reduced_rating(
@@ -33,35 +51,127 @@ fn calculate_reduced_rating(
     nums: &[u16],
     bit_selector: impl for<'a> Fn(u8, &'a [u16]) -> u16,
 ) -> u16 {
-    if nums.len() == 1 {
-        return nums[0];
+    match reduce_by_bit_criteria(bit_count, nums, bit_selector)[..] {
+        [num] => num,
+        _ => panic!("Unexpected edge case"),
     }
+}
 
+/// Filters `nums` by the bit criteria until one number remains or all bits
+/// have been processed, returning the survivors
+fn reduce_by_bit_criteria(
+    bit_count: u8,
+    nums: &[u16],
+    bit_selector: impl for<'a> Fn(u8, &'a [u16]) -> u16,
+) -> Vec<u16> {
     let mut nums = nums.to_vec();
     for bit in (0..bit_count).rev() {
+        if nums.len() <= 1 {
+            break;
+        }
+
         let selector = bit_selector(bit, &nums);
-        nums = nums
+        let filtered: Vec<_> = nums
             .iter()
             .filter(|num| (*num >> bit) & 1 == selector)
             .copied()
             .collect();
-        if nums.len() == 1 {
-            return nums[0];
+
+        // If all remaining numbers share this bit, the least common bit
+        // would eliminate every number, so leave them as is
+        if !filtered.is_empty() {
+            nums = filtered;
         }
     }
+    nums
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RatingError {
+    NoNumbers,
+    MultipleSurvivors(Vec<u16>),
+}
+
+/// Applies the bit criteria used for the oxygen generator rating (if
+/// `prefer_one_on_tie`) or the CO2 scrubber rating (otherwise), reporting an
+/// error rather than panicking if a single number does not remain
+pub fn find_unique_number(
+    bit_count: u8,
+    nums: &[u16],
+    prefer_one_on_tie: bool,
+) -> Result<u16, RatingError> {
+    let survivors = if prefer_one_on_tie {
+        reduce_by_bit_criteria(bit_count, nums, most_common_bit)
+    } else {
+        reduce_by_bit_criteria(bit_count, nums, least_common_bit)
+    };
+
+    match survivors[..] {
+        [] => Err(RatingError::NoNumbers),
+        [num] => Ok(num),
+        _ => Err(RatingError::MultipleSurvivors(survivors)),
+    }
+}
 
-    panic!("Unexpected edge case");
+pub fn calculate_oxygen_rating(bit_count: u8, nums: &[u16]) -> u16 {
+    calculate_reduced_rating(bit_count, nums, |bit, nums| {
+        most_common_bit_with_tiebreak(bit, nums, 1)
+    })
 }
 
-fn calculate_oxygen_rating(bit_count: u8, nums: &[u16]) -> u16 {
-    calculate_reduced_rating(bit_count, nums, most_common_bit)
+pub fn calculate_co2_rating(bit_count: u8, nums: &[u16]) -> u16 {
+    calculate_reduced_rating(bit_count, nums, |bit, nums| {
+        least_common_bit_with_tiebreak(bit, nums, 0)
+    })
 }
 
-fn calculate_co2_rating(bit_count: u8, nums: &[u16]) -> u16 {
-    calculate_reduced_rating(bit_count, nums, least_common_bit)
+/// Diagnostic numbers of a fixed bit width
+pub struct DiagnosticReport<'a, const BITS: u8> {
+    nums: &'a [u16],
+}
+
+impl<'a, const BITS: u8> DiagnosticReport<'a, BITS> {
+    pub fn new(nums: &'a [u16]) -> Self {
+        Self { nums }
+    }
+
+    fn validate(&self) -> Result<(), &'static str> {
+        if BITS == 0 || BITS > 16 {
+            return Err("Bit width must be between 1 and 16");
+        }
+        if self.nums.is_empty() {
+            return Err("No numbers in report");
+        }
+        Ok(())
+    }
+
+    pub fn gamma(&self) -> Result<u16, &'static str> {
+        self.validate()?;
+        Ok(calculate_gamma(BITS, self.nums))
+    }
+
+    pub fn epsilon(&self) -> Result<u16, &'static str> {
+        Ok(calculate_epsilon(BITS, self.gamma()?))
+    }
+
+    pub fn oxygen_rating(&self) -> Result<u16, &'static str> {
+        self.reduced_rating(true)
+    }
+
+    pub fn co2_rating(&self) -> Result<u16, &'static str> {
+        self.reduced_rating(false)
+    }
+
+    fn reduced_rating(&self, prefer_one_on_tie: bool) -> Result<u16, &'static str> {
+        self.validate()?;
+        find_unique_number(BITS, self.nums, prefer_one_on_tie).map_err(|err| match err {
+            RatingError::NoNumbers => "No numbers in report",
+            RatingError::MultipleSurvivors(_) => "Multiple numbers satisfy the bit criteria",
+        })
+    }
 }
 
-fn main() {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     let filename = "src/bin/day3/input.txt";
     let file = File::open(filename).expect("File not found");
     let reader = BufReader::new(file);
@@ -71,8 +181,9 @@ fn main() {
         .map(|l| u16::from_str_radix(&l.unwrap(), 2).unwrap())
         .collect();
 
-    let gamma = calculate_gamma(BIT_COUNT, &nums);
-    let epsilon = calculate_epsilon(BIT_COUNT, gamma);
+    let report = DiagnosticReport::<12>::new(&nums);
+    let gamma = report.gamma()?;
+    let epsilon = report.epsilon()?;
     println!("Gamma: {}", gamma);
     println!("Epsilon: {}", epsilon);
     println!(
@@ -81,14 +192,16 @@ fn main() {
     );
 
     println!();
-    let oxygen = calculate_oxygen_rating(BIT_COUNT, &nums);
-    let co2 = calculate_co2_rating(BIT_COUNT, &nums);
+    let oxygen = report.oxygen_rating()?;
+    let co2 = report.co2_rating()?;
     println!("Oxygen generator rating: {}", oxygen);
     println!("CO2 scrubber rating: {}", co2);
     println!(
         "Life support rating (product of above): {}",
         oxygen as u32 * co2 as u32
     );
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -106,6 +219,38 @@ mod tests {
         assert_eq!(gamma, 0b10110);
         let epsilon = calculate_epsilon(5, gamma);
         assert_eq!(epsilon, 0b01001);
+
+        assert_eq!(calculate_epsilon(16, 0xF0F0), 0x0F0F);
+        assert_eq!(calculate_epsilon(0, 0xF0F0), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "tiebreak must be 0 or 1")]
+    fn test_invalid_tiebreak() {
+        most_common_bit_with_tiebreak(0, &[0b1, 0b0], 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "tiebreak must be 0 or 1")]
+    fn test_invalid_least_common_tiebreak() {
+        least_common_bit_with_tiebreak(0, &[0b1, 0b0], 2);
+    }
+
+    #[test]
+    fn test_bit_tiebreak() {
+        let nums = &[0b10, 0b01, 0b11, 0b00];
+        assert_eq!(most_common_bit_with_tiebreak(0, nums, 0), 0);
+        assert_eq!(most_common_bit_with_tiebreak(1, nums, 1), 1);
+        assert_eq!(least_common_bit_with_tiebreak(0, nums, 0), 0);
+        assert_eq!(least_common_bit_with_tiebreak(0, nums, 1), 1);
+        assert_eq!(most_common_bit(0, nums), 1);
+        assert_eq!(least_common_bit(0, nums), 0);
+
+        // No tie
+        let nums = &[0b1, 0b0, 0b0];
+        assert_eq!(most_common_bit_with_tiebreak(0, nums, 1), 0);
+        assert_eq!(least_common_bit_with_tiebreak(0, nums, 0), 1);
+        assert_eq!(most_common_bit_with_tiebreak(0, &[], 1), 1);
     }
 
     #[test]
@@ -115,4 +260,57 @@ mod tests {
         let co2 = calculate_co2_rating(5, EXAMPLE);
         assert_eq!(co2, 10);
     }
+
+    #[test]
+    fn test_find_unique_number() {
+        assert_eq!(find_unique_number(5, EXAMPLE, true), Ok(23));
+        assert_eq!(find_unique_number(5, EXAMPLE, false), Ok(10));
+
+        let nums = &[0b101, 0b101, 0b010];
+        assert_eq!(
+            find_unique_number(3, nums, true),
+            Err(RatingError::MultipleSurvivors(vec![0b101, 0b101]))
+        );
+        assert_eq!(find_unique_number(3, nums, false), Ok(0b010));
+
+        let nums = &[0b110, 0b110];
+        assert_eq!(
+            find_unique_number(3, nums, false),
+            Err(RatingError::MultipleSurvivors(vec![0b110, 0b110]))
+        );
+
+        assert_eq!(
+            find_unique_number(3, &[], true),
+            Err(RatingError::NoNumbers)
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_report() {
+        let report = DiagnosticReport::<5>::new(EXAMPLE);
+        assert_eq!(report.gamma(), Ok(0b10110));
+        assert_eq!(report.epsilon(), Ok(0b01001));
+        assert_eq!(report.oxygen_rating(), Ok(23));
+        assert_eq!(report.co2_rating(), Ok(10));
+
+        let report = DiagnosticReport::<5>::new(&[]);
+        assert!(report.gamma().is_err());
+        assert!(report.oxygen_rating().is_err());
+
+        let report = DiagnosticReport::<3>::new(&[0b101, 0b101]);
+        assert!(report.oxygen_rating().is_err());
+        assert!(report.co2_rating().is_err());
+
+        let nums = &[
+            0b1000_0000_0000_0001,
+            0b1000_0000_0000_0000,
+            0b0000_0000_0000_0001,
+        ];
+        let report = DiagnosticReport::<16>::new(nums);
+        assert_eq!(report.gamma(), Ok(0b1000_0000_0000_0001));
+        assert_eq!(report.epsilon(), Ok(0b0111_1111_1111_1110));
+
+        let report = DiagnosticReport::<17>::new(EXAMPLE);
+        assert!(report.epsilon().is_err());
+    }
 }
@@ -1,6 +1,7 @@
 use std::{
+    fmt,
     fs::File,
-    io::{BufRead, BufReader, Lines},
+    io::{self, BufRead, BufReader},
 };
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -25,32 +26,101 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 #[derive(Debug, Clone)]
-struct Game {
+pub struct Game {
     numbers_drawn: Vec<u32>,
     boards: Vec<Board>,
 }
 
-const BOARD_WIDTH: usize = 5;
-
 #[derive(Debug, Clone)]
-struct Board {
-    drawn: [bool; BOARD_WIDTH * BOARD_WIDTH],
+pub struct Board {
+    /// Number of rows (and columns)
+    size: usize,
+    drawn: Vec<bool>,
     nums: Vec<u32>,
+    winning_pattern: Option<BingoPattern>,
+}
+
+/// The outcome of drawing a single number
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrawEvent {
+    pub drawn_number: u32,
+    /// Indices of the boards which won as a result of this draw
+    pub newly_won_boards: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BingoPattern {
+    Row(usize),
+    Column(usize),
+}
+
+#[derive(Debug)]
+pub enum Day4Error {
+    Io(io::Error),
+    /// A line of numbers was expected, but the input ended
+    ExpectedNumbers,
+    InvalidInteger,
+    /// A board row held the given number of integers, rather than one per row
+    WrongRowLength(usize),
+    /// The input had no boards
+    MissingSection,
+}
+
+impl fmt::Display for Day4Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Day4Error::Io(err) => write!(f, "Failed to read input: {}", err),
+            Day4Error::ExpectedNumbers => write!(f, "Expected list of numbers"),
+            Day4Error::InvalidInteger => write!(f, "Expected integer"),
+            Day4Error::WrongRowLength(len) => write!(
+                f,
+                "Each row should contain as many integers as there are rows (found {})",
+                len
+            ),
+            Day4Error::MissingSection => write!(f, "Expected board"),
+        }
+    }
+}
+
+impl std::error::Error for Day4Error {}
+
+impl From<io::Error> for Day4Error {
+    fn from(err: io::Error) -> Self {
+        Day4Error::Io(err)
+    }
 }
 
 impl Game {
-    fn parse(input: impl BufRead) -> Result<Game, &'static str> {
-        let mut lines = input.lines();
+    fn parse(input: impl BufRead) -> Result<Game, Day4Error> {
+        let mut lines = input.lines().peekable();
 
         let numbers_drawn = parse_integer_line(&mut lines, ',')?;
 
         let mut boards = vec![];
+        let mut size = None;
         loop {
-            if lines.next().is_none() {
-                break;
+            match lines.next() {
+                None => break,
+                Some(Err(err)) => return Err(err.into()),
+                Some(Ok(_)) => {}
             }
 
-            boards.push(Board::parse(&mut lines)?);
+            if let Some(Err(_)) = lines.peek() {
+                if let Some(Err(err)) = lines.next() {
+                    return Err(err.into());
+                }
+            }
+
+            // All boards are square, and the same size as the first
+            let size = *size.get_or_insert_with(|| match lines.peek() {
+                Some(Ok(row)) => row.split_whitespace().count(),
+                _ => 0,
+            });
+            if size == 0 {
+                return Err(Day4Error::MissingSection);
+            }
+
+            boards.push(Board::parse(&mut lines, size)?);
         }
 
         Ok(Game {
@@ -59,16 +129,47 @@ impl Game {
         })
     }
 
-    fn play(mut self) -> Option<u64> {
-        for drawn in self.numbers_drawn {
-            for board in &mut self.boards {
-                if board.draw(drawn) {
-                    return Some(board.score(drawn));
+    /// Lazily draws each number in turn on a copy of the boards
+    pub fn simulate_iterator(&self) -> impl Iterator<Item = DrawEvent> + '_ {
+        let mut boards = self.boards.clone();
+        self.numbers_drawn.iter().map(move |&drawn_number| {
+            let mut newly_won_boards = Vec::new();
+            for (idx, board) in boards.iter_mut().enumerate() {
+                let was_won = board.is_won();
+                if board.draw(drawn_number) && !was_won {
+                    newly_won_boards.push(idx);
+                }
+            }
+
+            DrawEvent {
+                drawn_number,
+                newly_won_boards,
+            }
+        })
+    }
+
+    fn play(self) -> Option<u64> {
+        self.play_first_n_winners(1).pop()
+    }
+
+    /// Returns the scores of the first `n` boards to win, in the order they
+    /// win. Fewer scores are returned if fewer boards ever win
+    fn play_first_n_winners(&self, n: usize) -> Vec<u64> {
+        let mut boards = self.boards.clone();
+        let mut scores = Vec::with_capacity(n);
+        for &drawn in &self.numbers_drawn {
+            for board in &mut boards {
+                if scores.len() == n {
+                    return scores;
+                }
+
+                if !board.is_won() && board.draw(drawn) {
+                    scores.push(board.score(drawn));
                 }
             }
         }
 
-        None
+        scores
     }
 
     fn play_to_lose(mut self) -> Option<u64> {
@@ -93,19 +194,25 @@ impl Game {
 }
 
 impl Board {
-    fn parse(lines: &mut Lines<impl BufRead>) -> Result<Board, &'static str> {
-        let mut board = Vec::with_capacity(BOARD_WIDTH * BOARD_WIDTH);
-        for _ in 0..BOARD_WIDTH {
+    /// Reads a board with `size` rows, each of `size` integers
+    fn parse(
+        lines: &mut impl Iterator<Item = io::Result<String>>,
+        size: usize,
+    ) -> Result<Board, Day4Error> {
+        let mut board = Vec::with_capacity(size * size);
+        for _ in 0..size {
             let nums = parse_integer_line_ws(lines)?;
-            if nums.len() != BOARD_WIDTH {
-                return Err("Each row should contain 5 integers");
+            if nums.len() != size {
+                return Err(Day4Error::WrongRowLength(nums.len()));
             }
             board.extend_from_slice(&nums);
         }
 
         Ok(Board {
+            size,
             nums: board,
-            drawn: [false; BOARD_WIDTH * BOARD_WIDTH],
+            drawn: vec![false; size * size],
+            winning_pattern: None,
         })
     }
 
@@ -119,22 +226,65 @@ impl Board {
             }
         }
 
+        if self.winning_pattern.is_none() {
+            self.winning_pattern = self.find_winning_pattern();
+        }
+        self.winning_pattern.is_some()
+    }
+
+    /// The row or column which completed when the board first won. Lines
+    /// completed by later draws don't change it
+    pub fn winning_pattern(&self) -> Option<BingoPattern> {
+        self.winning_pattern
+    }
+
+    pub fn is_won(&self) -> bool {
+        self.find_winning_pattern().is_some()
+    }
+
+    /// Clears all drawn numbers
+    pub fn reset(&mut self) {
+        self.drawn.iter_mut().for_each(|drawn| *drawn = false);
+        self.winning_pattern = None;
+    }
+
+    /// Replays the draws on a fresh copy of the board, giving the score after
+    /// each draw. The score is `None` until the board wins, and then stays the
+    /// same
+    pub fn score_sequence(&self, drawn_numbers: &[u32]) -> Vec<Option<u64>> {
+        let mut board = self.clone();
+        board.reset();
+
+        let mut score = None;
+        drawn_numbers
+            .iter()
+            .map(|&num| {
+                if score.is_none() && board.draw(num) {
+                    score = Some(board.score(num));
+                }
+                score
+            })
+            .collect()
+    }
+
+    fn find_winning_pattern(&self) -> Option<BingoPattern> {
         let winning_row = self
             .drawn
-            .chunks_exact(BOARD_WIDTH)
-            .any(|row| row.iter().all(|&x| x));
-        if winning_row {
-            return true;
+            .chunks_exact(self.size)
+            .position(|row| row.iter().all(|&x| x));
+        if let Some(row) = winning_row {
+            return Some(BingoPattern::Row(row));
         }
 
         // Check for winning col
-        (0..BOARD_WIDTH).any(|col| {
-            (col..)
-                .step_by(BOARD_WIDTH)
-                .take(5)
-                .map(|idx| self.drawn[idx])
-                .all(|x| x)
-        })
+        (0..self.size)
+            .find(|&col| {
+                (col..)
+                    .step_by(self.size)
+                    .take(self.size)
+                    .all(|idx| self.drawn[idx])
+            })
+            .map(BingoPattern::Column)
     }
 
     fn score(&self, last_drawn: u32) -> u64 {
@@ -150,26 +300,26 @@ impl Board {
 }
 
 fn parse_integer_line(
-    lines: &mut Lines<impl BufRead>,
+    lines: &mut impl Iterator<Item = io::Result<String>>,
     sep: char,
-) -> Result<Vec<u32>, &'static str> {
+) -> Result<Vec<u32>, Day4Error> {
     let integers = lines
         .next()
-        .ok_or("Expected list of numbers")?
-        .map_err(|_| "I/O Error reading line")?
+        .ok_or(Day4Error::ExpectedNumbers)??
         .split(sep)
-        .map(|s| s.parse().map_err(|_| "Expected integer"))
+        .map(|s| s.parse().map_err(|_| Day4Error::InvalidInteger))
         .collect::<Result<Vec<u32>, _>>()?;
     Ok(integers)
 }
 
-fn parse_integer_line_ws(lines: &mut Lines<impl BufRead>) -> Result<Vec<u32>, &'static str> {
+fn parse_integer_line_ws(
+    lines: &mut impl Iterator<Item = io::Result<String>>,
+) -> Result<Vec<u32>, Day4Error> {
     let integers = lines
         .next()
-        .ok_or("Expected list of numbers")?
-        .map_err(|_| "I/O Error reading line")?
+        .ok_or(Day4Error::ExpectedNumbers)??
         .split_whitespace()
-        .map(|s| s.parse().map_err(|_| "Expected integer"))
+        .map(|s| s.parse().map_err(|_| Day4Error::InvalidInteger))
         .collect::<Result<Vec<u32>, _>>()?;
     Ok(integers)
 }
@@ -217,6 +367,29 @@ mod tests {
         assert_eq!(input.unwrap(), vec![3, 4, 5]);
     }
 
+    #[test]
+    fn test_parse_errors() {
+        let short_row = TEST_INPUT.replacen(" 8  2 23  4 24", " 8  2 23  4", 1);
+        let result = Game::parse(io::Cursor::new(short_row));
+        assert!(matches!(result, Err(Day4Error::WrongRowLength(4))));
+
+        let result = Game::parse(io::Cursor::new(""));
+        assert!(matches!(result, Err(Day4Error::ExpectedNumbers)));
+
+        let result = Game::parse(io::Cursor::new("1,2,x\n"));
+        assert!(matches!(result, Err(Day4Error::InvalidInteger)));
+
+        let result = Game::parse(io::Cursor::new("1,2,3\n\n"));
+        assert!(matches!(result, Err(Day4Error::MissingSection)));
+
+        let result = Game::parse(io::Cursor::new(b"1,2\n\n\xff\n".as_ref()));
+        assert!(matches!(result, Err(Day4Error::Io(_))));
+
+        // Unreadable separator line before a board
+        let result = Game::parse(io::Cursor::new(b"1,2\n\xff\n1 2\n3 4\n".as_ref()));
+        assert!(matches!(result, Err(Day4Error::Io(_))));
+    }
+
     #[test]
     fn test_game() {
         let game = Game::parse(&mut io::Cursor::new(TEST_INPUT)).unwrap();
@@ -228,4 +401,157 @@ mod tests {
         let game = Game::parse(&mut io::Cursor::new(TEST_INPUT)).unwrap();
         assert_eq!(game.play_to_lose(), Some(1924));
     }
+
+    #[test]
+    fn test_play_first_n_winners() {
+        let game = Game::parse(&mut io::Cursor::new(TEST_INPUT)).unwrap();
+        assert_eq!(game.play_first_n_winners(0), []);
+        assert_eq!(game.play_first_n_winners(1), [4512]);
+        assert_eq!(game.play_first_n_winners(3), [4512, 2192, 1924]);
+        assert_eq!(game.play_first_n_winners(10), [4512, 2192, 1924]);
+
+        // Only the first board can win
+        let input = "1,2\n\n1 2\n3 4\n\n1 3\n5 6\n";
+        let game = Game::parse(&mut io::Cursor::new(input)).unwrap();
+        assert_eq!(game.play_first_n_winners(2), [14]);
+    }
+
+    #[test]
+    fn test_simulate_iterator() {
+        let game = Game::parse(&mut io::Cursor::new(TEST_INPUT)).unwrap();
+        assert_eq!(game.simulate_iterator().count(), game.numbers_drawn.len());
+
+        let first_win = game
+            .simulate_iterator()
+            .find(|event| !event.newly_won_boards.is_empty())
+            .unwrap();
+        assert_eq!(
+            first_win,
+            DrawEvent {
+                drawn_number: 24,
+                newly_won_boards: vec![2]
+            }
+        );
+
+        // Every board wins exactly once
+        let won: Vec<_> = game
+            .simulate_iterator()
+            .flat_map(|event| event.newly_won_boards)
+            .collect();
+        assert_eq!(won, [2, 0, 1]);
+
+        let last_win = game
+            .simulate_iterator()
+            .filter(|event| !event.newly_won_boards.is_empty())
+            .last()
+            .unwrap();
+        assert_eq!(last_win.drawn_number, 13);
+        assert_eq!(last_win.newly_won_boards, [1]);
+    }
+
+    #[test]
+    fn test_score_sequence() {
+        let game = Game::parse(&mut io::Cursor::new(TEST_INPUT)).unwrap();
+        let drawn = &game.numbers_drawn;
+        let scores = game.boards[2].score_sequence(drawn);
+        assert_eq!(scores.len(), drawn.len());
+
+        let win_index = drawn.iter().position(|&num| num == 24).unwrap();
+        assert!(scores[..win_index].iter().all(Option::is_none));
+        assert!(scores[win_index..].iter().all(|&score| score == Some(4512)));
+
+        // The board itself is untouched, even if partially played
+        let mut board = game.boards[2].clone();
+        board.draw(14);
+        assert_eq!(board.score_sequence(drawn), scores);
+        assert_eq!(board.drawn.iter().filter(|&&drawn| drawn).count(), 1);
+
+        assert_eq!(board.score_sequence(&[]), []);
+    }
+
+    #[test]
+    fn test_is_won_reset() {
+        let game = Game::parse(&mut io::Cursor::new(TEST_INPUT)).unwrap();
+        let mut board = game.boards[0].clone();
+        assert_eq!(board.size, 5);
+        assert!(!board.is_won());
+
+        for num in [22, 8, 21, 6, 1] {
+            assert!(!board.is_won());
+            board.draw(num);
+        }
+        assert!(board.is_won());
+        assert!(board.is_won());
+
+        board.reset();
+        assert!(!board.is_won());
+        assert_eq!(board.winning_pattern(), None);
+        assert!(board.drawn.iter().all(|&drawn| !drawn));
+    }
+
+    #[test]
+    fn test_board_size() {
+        const SMALL_INPUT: &str = "\
+1,2,3,4,5,6
+
+1 2 3
+4 5 6
+7 8 9
+
+9 8 7
+6 5 4
+2 3 10
+";
+        let game = Game::parse(&mut io::Cursor::new(SMALL_INPUT)).unwrap();
+        assert_eq!(game.boards.len(), 2);
+        assert!(game.boards.iter().all(|board| board.size == 3));
+        // First board wins with its top row on 3, second with its middle row
+        // on 6
+        assert_eq!(game.clone().play(), Some((4 + 5 + 6 + 7 + 8 + 9) * 3));
+        assert_eq!(game.play_to_lose(), Some((9 + 8 + 7 + 10) * 6));
+
+        // Boards must be square
+        let result = Game::parse(&mut io::Cursor::new("1,2\n\n1 2\n3 4\n5 6\n"));
+        assert!(result.is_err());
+        let result = Game::parse(&mut io::Cursor::new("1,2\n\n1 2\n3 4\n\n1 2 3\n"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_winning_pattern() {
+        let game = Game::parse(&mut io::Cursor::new(TEST_INPUT)).unwrap();
+
+        // The third board wins first, with its top row
+        let mut board = game.boards[2].clone();
+        for &num in game.numbers_drawn.iter() {
+            assert_eq!(board.winning_pattern(), None);
+            if board.draw(num) {
+                break;
+            }
+        }
+        assert_eq!(board.winning_pattern(), Some(BingoPattern::Row(0)));
+
+        // Left column of the first board
+        let mut board = game.boards[0].clone();
+        for num in [22, 8, 21, 6] {
+            assert!(!board.draw(num));
+        }
+        assert!(board.draw(1));
+        assert_eq!(board.winning_pattern(), Some(BingoPattern::Column(0)));
+
+        // Completing the top row as well keeps the original pattern
+        for num in [13, 17, 11, 0] {
+            assert!(board.draw(num));
+        }
+        assert_eq!(board.find_winning_pattern(), Some(BingoPattern::Row(0)));
+        assert_eq!(board.winning_pattern(), Some(BingoPattern::Column(0)));
+
+        // Bottom row of the second board
+        let mut board = game.boards[1].clone();
+        for num in [14, 21, 16, 12] {
+            assert!(!board.draw(num));
+        }
+        assert!(board.draw(6));
+        assert_eq!(board.winning_pattern(), Some(BingoPattern::Row(4)));
+    }
 }
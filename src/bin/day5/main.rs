@@ -1,4 +1,5 @@
 use std::{
+    collections::{HashMap, HashSet, VecDeque},
     fs::File,
     io::{BufRead, BufReader},
 };
@@ -29,12 +30,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-struct Grid {
-    #[cfg(test)]
+/// Counts of lines covering each point
+pub trait IntersectionGrid {
+    fn count_intersections(&self) -> usize;
+
+    /// Points covered by more than one line
+    fn intersections(&self) -> Box<dyn Iterator<Item = (u32, u32)> + '_>;
+}
+
+pub struct Grid {
     left: u32,
-    #[cfg(test)]
     width: u32,
-    #[cfg(test)]
     top: u32,
     counts: Vec<usize>,
 }
@@ -59,38 +65,154 @@ impl Grid {
         }
 
         Some(Self {
-            #[cfg(test)]
             left,
-            #[cfg(test)]
             width,
-            #[cfg(test)]
             top,
             counts,
         })
     }
 
-    #[cfg(test)]
-    fn intersections(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+    /// Combines two grids by summing their counts. Returns `None` if the grids
+    /// do not cover the same area
+    pub fn overlay(&self, other: &Grid) -> Option<Grid> {
+        self.combine(other, |a, b| a + b)
+    }
+
+    /// Subtracts the counts of `other` from this grid (clamping at 0). Returns
+    /// `None` if the grids do not cover the same area
+    pub fn difference(&self, other: &Grid) -> Option<Grid> {
+        self.combine(other, usize::saturating_sub)
+    }
+
+    /// Groups the points covered by at least one line into 4-connected
+    /// components, keyed by an arbitrary id. Points within a component are in
+    /// row-major order
+    pub fn connected_components(&self) -> HashMap<usize, Vec<(u32, u32)>> {
         let width = self.width as usize;
-        let left = self.left;
-        let top = self.top;
+        let height = self.counts.len() / width;
+
+        let mut components = HashMap::new();
+        let mut visited = vec![false; self.counts.len()];
+        for start in 0..self.counts.len() {
+            if visited[start] || self.counts[start] == 0 {
+                continue;
+            }
 
-        self.counts
+            let mut component = Vec::new();
+            let mut queue = VecDeque::from([start]);
+            visited[start] = true;
+            while let Some(idx) = queue.pop_front() {
+                component.push(idx);
+
+                let (x, y) = (idx % width, idx / width);
+                let neighbors = [
+                    (x > 0).then(|| idx - 1),
+                    (x + 1 < width).then(|| idx + 1),
+                    (y > 0).then(|| idx - width),
+                    (y + 1 < height).then(|| idx + width),
+                ];
+                for neighbor in neighbors.iter().flatten().copied() {
+                    if !visited[neighbor] && self.counts[neighbor] > 0 {
+                        visited[neighbor] = true;
+                        queue.push_back(neighbor);
+                    }
+                }
+            }
+
+            component.sort_unstable();
+            let points = component
+                .into_iter()
+                .map(|idx| {
+                    (
+                        (idx % width) as u32 + self.left,
+                        (idx / width) as u32 + self.top,
+                    )
+                })
+                .collect();
+            components.insert(components.len(), points);
+        }
+        components
+    }
+
+    fn combine(&self, other: &Grid, f: impl Fn(usize, usize) -> usize) -> Option<Grid> {
+        if self.left != other.left
+            || self.top != other.top
+            || self.width != other.width
+            || self.counts.len() != other.counts.len()
+        {
+            return None;
+        }
+
+        let counts = self
+            .counts
             .iter()
-            .copied()
-            .enumerate()
-            .filter(|&(_, count)| count > 1)
-            .map(move |(idx, _)| ((idx % width) as u32 + left, (idx / width) as u32 + top))
+            .zip(other.counts.iter())
+            .map(|(&a, &b)| f(a, b))
+            .collect();
+        Some(Self {
+            left: self.left,
+            width: self.width,
+            top: self.top,
+            counts,
+        })
     }
+}
 
+impl IntersectionGrid for Grid {
     fn count_intersections(&self) -> usize {
         self.counts.iter().filter(|&&c| c > 1).count()
     }
+
+    fn intersections(&self) -> Box<dyn Iterator<Item = (u32, u32)> + '_> {
+        let width = self.width as usize;
+        let left = self.left;
+        let top = self.top;
+
+        Box::new(
+            self.counts
+                .iter()
+                .copied()
+                .enumerate()
+                .filter(|&(_, count)| count > 1)
+                .map(move |(idx, _)| ((idx % width) as u32 + left, (idx / width) as u32 + top)),
+        )
+    }
+}
+
+/// Like `Grid`, but only stores points which are covered by a line, so it can
+/// handle lines spread over a very large area
+pub struct SparseGrid {
+    counts: HashMap<(u32, u32), usize>,
+}
+
+impl SparseGrid {
+    pub fn from(lines: &[Line]) -> Self {
+        let mut counts = HashMap::new();
+        for line in lines {
+            line.map_points(|point| *counts.entry(point).or_insert(0) += 1);
+        }
+        Self { counts }
+    }
+}
+
+impl IntersectionGrid for SparseGrid {
+    fn count_intersections(&self) -> usize {
+        self.counts.values().filter(|&&c| c > 1).count()
+    }
+
+    fn intersections(&self) -> Box<dyn Iterator<Item = (u32, u32)> + '_> {
+        Box::new(
+            self.counts
+                .iter()
+                .filter(|&(_, &count)| count > 1)
+                .map(|(&point, _)| point),
+        )
+    }
 }
 
 // NOTE: x1 <= x2 is guaranteed by construction
 #[derive(Debug, PartialEq, Eq, Clone)]
-struct Line {
+pub struct Line {
     x1: u32,
     y1: u32,
     x2: u32,
@@ -135,6 +257,87 @@ impl Line {
         }
     }
 
+    /// Euclidean length of the segment
+    pub fn length(&self) -> f64 {
+        let dx = self.x2 as f64 - self.x1 as f64;
+        let dy = self.y2 as f64 - self.y1 as f64;
+        dx.hypot(dy)
+    }
+
+    /// Returns where the (infinite) lines through each segment meet, or `None`
+    /// if they are parallel
+    pub fn intersect_point(a: &Line, b: &Line) -> Option<(f64, f64)> {
+        let (x1, y1, x2, y2) = a.as_f64();
+        let (x3, y3, x4, y4) = b.as_f64();
+
+        let denominator = (x1 - x2) * (y3 - y4) - (y1 - y2) * (x3 - x4);
+        if denominator == 0. {
+            return None;
+        }
+
+        let det_a = x1 * y2 - y1 * x2;
+        let det_b = x3 * y4 - y3 * x4;
+        let x = (det_a * (x3 - x4) - (x1 - x2) * det_b) / denominator;
+        let y = (det_a * (y3 - y4) - (y1 - y2) * det_b) / denominator;
+        Some((x, y))
+    }
+
+    /// Returns `true` if the two segments share at least one point (which need
+    /// not lie on the integer grid)
+    pub fn segments_intersect(a: &Line, b: &Line) -> bool {
+        let p1 = (a.x1 as i64, a.y1 as i64);
+        let p2 = (a.x2 as i64, a.y2 as i64);
+        let p3 = (b.x1 as i64, b.y1 as i64);
+        let p4 = (b.x2 as i64, b.y2 as i64);
+
+        // Sign of the cross product of (q - p) and (r - p)
+        fn orientation(p: (i64, i64), q: (i64, i64), r: (i64, i64)) -> i64 {
+            ((q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0)).signum()
+        }
+
+        // Whether `q`, known to be collinear with `p` and `r`, lies between them
+        fn within(p: (i64, i64), q: (i64, i64), r: (i64, i64)) -> bool {
+            q.0 >= p.0.min(r.0) && q.0 <= p.0.max(r.0) && q.1 >= p.1.min(r.1) && q.1 <= p.1.max(r.1)
+        }
+
+        let o1 = orientation(p1, p2, p3);
+        let o2 = orientation(p1, p2, p4);
+        let o3 = orientation(p3, p4, p1);
+        let o4 = orientation(p3, p4, p2);
+
+        (o1 != o2 && o3 != o4)
+            || (o1 == 0 && within(p1, p3, p2))
+            || (o2 == 0 && within(p1, p4, p2))
+            || (o3 == 0 && within(p3, p1, p4))
+            || (o4 == 0 && within(p3, p2, p4))
+    }
+
+    /// Returns `true` if the two segments cover at least one common point on
+    /// the integer grid. Unlike `segments_intersect`, diagonals crossing
+    /// between grid points do not count
+    pub fn segments_touching(a: &Line, b: &Line) -> bool {
+        if !Self::segments_intersect(a, b) {
+            return false;
+        }
+
+        let mut points = HashSet::new();
+        a.map_points(|p| {
+            points.insert(p);
+        });
+        let mut touching = false;
+        b.map_points(|p| touching |= points.contains(&p));
+        touching
+    }
+
+    fn as_f64(&self) -> (f64, f64, f64, f64) {
+        (
+            self.x1 as f64,
+            self.y1 as f64,
+            self.x2 as f64,
+            self.y2 as f64,
+        )
+    }
+
     fn map_points(&self, mut f: impl FnMut((u32, u32))) {
         match self.kind() {
             LineKind::Vertical => {
@@ -292,4 +495,166 @@ mod tests {
         let grid = Grid::from(&lines).unwrap();
         assert_eq!(grid.count_intersections(), 12);
     }
+
+    #[test]
+    fn test_line_geometry() {
+        assert_eq!(Line::new(0, 9, 5, 9).length(), 5.);
+        assert_eq!(Line::new(7, 0, 7, 4).length(), 4.);
+        assert_eq!(Line::new(3, 3, 3, 3).length(), 0.);
+        assert!((Line::new(0, 0, 8, 8).length() - 8. * 2f64.sqrt()).abs() < 1e-9);
+
+        // The two long diagonals in the example cross in the middle
+        let a = Line::new(8, 0, 0, 8);
+        let b = Line::new(0, 0, 8, 8);
+        assert_eq!(Line::intersect_point(&a, &b), Some((4., 4.)));
+        assert!(Line::segments_intersect(&a, &b));
+
+        // Parallel
+        let a = Line::new(0, 9, 5, 9);
+        let b = Line::new(3, 4, 9, 4);
+        assert_eq!(Line::intersect_point(&a, &b), None);
+        assert!(!Line::segments_intersect(&a, &b));
+
+        // The infinite lines meet, but the segments don't reach
+        let a = Line::new(0, 0, 2, 0);
+        let b = Line::new(5, 1, 5, 3);
+        assert_eq!(Line::intersect_point(&a, &b), Some((5., 0.)));
+        assert!(!Line::segments_intersect(&a, &b));
+
+        // Touching at an end, and overlapping collinear segments
+        assert!(Line::segments_intersect(
+            &Line::new(0, 0, 2, 0),
+            &Line::new(2, 0, 2, 5)
+        ));
+        assert!(Line::segments_intersect(
+            &Line::new(0, 9, 5, 9),
+            &Line::new(0, 9, 2, 9)
+        ));
+        assert!(!Line::segments_intersect(
+            &Line::new(0, 9, 1, 9),
+            &Line::new(3, 9, 5, 9)
+        ));
+    }
+
+    #[test]
+    fn test_segments_touching() {
+        // Crossing at a grid point, and at an end
+        let a = Line::new(8, 0, 0, 8);
+        assert!(Line::segments_touching(&a, &Line::new(0, 0, 8, 8)));
+        assert!(Line::segments_touching(&a, &Line::new(8, 0, 9, 0)));
+        assert!(Line::segments_touching(
+            &Line::new(0, 9, 5, 9),
+            &Line::new(2, 9, 8, 9)
+        ));
+
+        // Diagonals crossing between grid points
+        let a = Line::new(0, 1, 1, 0);
+        let b = Line::new(0, 0, 1, 1);
+        assert!(Line::segments_intersect(&a, &b));
+        assert!(!Line::segments_touching(&a, &b));
+
+        assert!(!Line::segments_touching(
+            &Line::new(0, 9, 5, 9),
+            &Line::new(9, 4, 3, 4)
+        ));
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let lines = parse_lines(io::Cursor::new(SHORT_INPUT)).unwrap();
+        let nondiagonals: Vec<_> = lines
+            .iter()
+            .filter(|l| l.kind() != LineKind::Diagonal)
+            .cloned()
+            .collect();
+
+        let components = Grid::from(&nondiagonals).unwrap().connected_components();
+        assert_eq!(components.len(), 2);
+        let mut sizes: Vec<_> = components.values().map(|c| c.len()).collect();
+        sizes.sort_unstable();
+        assert_eq!(sizes, [6, 7]);
+        assert!(components
+            .values()
+            .any(|c| c == &[(0, 9), (1, 9), (2, 9), (3, 9), (4, 9), (5, 9)]));
+
+        // Neighbouring diagonal points are not 4-connected, so most of the
+        // diagonal is split into single points, except where it meets the
+        // other lines
+        let components = Grid::from(&lines).unwrap().connected_components();
+        assert_eq!(components.len(), 7);
+        let mut sizes: Vec<_> = components.values().map(|c| c.len()).collect();
+        sizes.sort_unstable();
+        assert_eq!(sizes, [1, 1, 1, 1, 1, 7, 9]);
+        assert!(components.values().any(|c| c == &[(2, 6)]));
+    }
+
+    #[test]
+    fn test_sparse_grid() {
+        let lines = parse_lines(io::Cursor::new(TEST_INPUT)).unwrap();
+        let nondiagonals: Vec<_> = lines
+            .iter()
+            .filter(|l| l.kind() != LineKind::Diagonal)
+            .cloned()
+            .collect();
+
+        for lines in [&nondiagonals, &lines] {
+            let dense = Grid::from(lines).unwrap();
+            let sparse = SparseGrid::from(lines);
+            assert_eq!(sparse.count_intersections(), dense.count_intersections());
+            assert_eq!(
+                sparse.intersections().collect::<HashSet<_>>(),
+                dense.intersections().collect::<HashSet<_>>()
+            );
+        }
+
+        // A dense grid would need a cell for every point in the bounding box
+        let lines = [
+            Line::new(0, 0, 10, 0),
+            Line::new(5, 0, 5, 5),
+            Line::new(1_000_000, 1_000_000, 999_990, 1_000_000),
+            Line::new(999_990, 999_990, 1_000_000, 1_000_000),
+        ];
+        let sparse = SparseGrid::from(&lines);
+        assert_eq!(sparse.count_intersections(), 2);
+        assert_eq!(
+            sparse.intersections().collect::<HashSet<_>>(),
+            HashSet::from([(5, 0), (1_000_000, 1_000_000)])
+        );
+    }
+
+    #[test]
+    fn test_overlay_difference() {
+        // Same bounding box, but no points in common
+        let first = [
+            Line::new(0, 0, 2, 0),
+            Line::new(0, 0, 0, 2),
+            Line::new(4, 4, 4, 4),
+        ];
+        let second = [
+            Line::new(4, 0, 3, 0),
+            Line::new(4, 0, 4, 2),
+            Line::new(0, 4, 2, 4),
+        ];
+        let first = Grid::from(&first).unwrap();
+        let second = Grid::from(&second).unwrap();
+        assert_eq!(first.count_intersections(), 1);
+        assert_eq!(second.count_intersections(), 1);
+
+        let overlay = first.overlay(&second).unwrap();
+        assert_eq!(overlay.count_intersections(), 2);
+        assert_eq!(
+            overlay.intersections().collect::<HashSet<_>>(),
+            HashSet::from([(0, 0), (4, 0)])
+        );
+
+        let difference = overlay.difference(&second).unwrap();
+        assert_eq!(difference.counts, first.counts);
+        let difference = first.difference(&overlay).unwrap();
+        assert!(difference.counts.iter().all(|&c| c == 0));
+
+        // Different bounding boxes
+        let other = Grid::from(&[Line::new(0, 0, 3, 3)]).unwrap();
+        assert!(first.overlay(&other).is_none());
+        assert!(first.difference(&other).is_none());
+    }
 }
@@ -1,5 +1,6 @@
 use std::{
     collections::HashMap,
+    convert::TryFrom,
     fs::File,
     io::{BufRead, BufReader},
 };
@@ -13,13 +14,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let reader = BufReader::new(file);
 
     let initial = parse_input(reader).ok_or("Failed to read input")?;
-    println!("After 80 days: {}", simulate(&initial, 80));
-    println!("After 256 days: {}", simulate(&initial, 256));
+    println!("After 80 days: {}", simulate_array(&initial, 80));
+    println!("After 256 days: {}", simulate_array(&initial, 256));
 
     Ok(())
 }
 
-fn simulate(fish: &[Lanternfish], cycles: usize) -> u64 {
+pub fn simulate(fish: &[Lanternfish], cycles: usize) -> u64 {
     let mut map: HashMap<Lanternfish, u64> = HashMap::new();
     for fish in fish {
         *map.entry(fish.clone()).or_insert(0) += 1;
@@ -32,6 +33,114 @@ fn simulate(fish: &[Lanternfish], cycles: usize) -> u64 {
     map.values().sum()
 }
 
+/// Equivalent to `simulate`, but keeps the number of fish with each timer value
+/// in an array rather than a map
+pub fn simulate_array(fish: &[Lanternfish], cycles: usize) -> u64 {
+    let mut counts = timer_counts(fish);
+    for _ in 0..cycles {
+        step_counts(&mut counts);
+    }
+
+    counts.iter().sum()
+}
+
+/// Total population after each of `0..=max_days` days
+pub fn population_curve(initial: &[Lanternfish], max_days: usize) -> Vec<u64> {
+    let mut counts = timer_counts(initial);
+    let mut curve = Vec::with_capacity(max_days + 1);
+    curve.push(counts.iter().sum());
+    for _ in 0..max_days {
+        step_counts(&mut counts);
+        curve.push(counts.iter().sum());
+    }
+    curve
+}
+
+/// Ratio of the population on `to_day` to the population on `from_day`
+pub fn growth_rate(initial: &[Lanternfish], from_day: usize, to_day: usize) -> f64 {
+    let curve = population_curve(initial, from_day.max(to_day));
+    curve[to_day] as f64 / curve[from_day] as f64
+}
+
+fn timer_counts(fish: &[Lanternfish]) -> [u64; TIMER_COUNT] {
+    let mut counts = [0u64; TIMER_COUNT];
+    for fish in fish {
+        counts[fish.0 as usize] += 1;
+    }
+    counts
+}
+
+fn step_counts(counts: &mut [u64; TIMER_COUNT]) {
+    // Fish with timer 0 move to the end of the array (as new fish), and are
+    // also added back with the repeat timer
+    counts.rotate_left(1);
+    counts[REPEAT_TIMER as usize] += counts[INITIAL_TIMER as usize];
+}
+
+const TIMER_COUNT: usize = INITIAL_TIMER as usize + 1;
+
+type TransitionMatrix = [[u128; TIMER_COUNT]; TIMER_COUNT];
+
+/// Equivalent to `simulate_array`, but raises the single-cycle transition
+/// matrix to the power of `cycles` by repeated squaring, so runs in
+/// O(log cycles) time
+pub fn simulate_fast(fish: &[Lanternfish], cycles: u64) -> u64 {
+    let mut counts = [0u128; TIMER_COUNT];
+    for fish in fish {
+        counts[fish.0 as usize] += 1;
+    }
+
+    // `step[i][j]` is the number of fish with timer `j` after one cycle
+    // resulting from a single fish with timer `i`
+    let mut step = [[0u128; TIMER_COUNT]; TIMER_COUNT];
+    for (i, row) in step.iter_mut().enumerate().skip(1) {
+        row[i - 1] = 1;
+    }
+    step[0][INITIAL_TIMER as usize] = 1;
+    step[0][REPEAT_TIMER as usize] = 1;
+
+    let transition = matrix_pow(step, cycles);
+    let total: u128 = counts
+        .iter()
+        .zip(transition.iter())
+        .map(|(&count, row)| count * row.iter().sum::<u128>())
+        .sum();
+    u64::try_from(total).expect("Population does not fit in a u64")
+}
+
+fn matrix_mul(a: &TransitionMatrix, b: &TransitionMatrix) -> TransitionMatrix {
+    let mut result = [[0u128; TIMER_COUNT]; TIMER_COUNT];
+    for (i, row) in result.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..TIMER_COUNT).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    result
+}
+
+fn matrix_pow(mut base: TransitionMatrix, mut exponent: u64) -> TransitionMatrix {
+    let mut result = [[0u128; TIMER_COUNT]; TIMER_COUNT];
+    for (i, row) in result.iter_mut().enumerate() {
+        row[i] = 1;
+    }
+
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = matrix_mul(&result, &base);
+        }
+        base = matrix_mul(&base, &base);
+        exponent >>= 1;
+    }
+    result
+}
+
+/// Population after `cycles` cycles, given the timer values of the initial
+/// fish
+pub fn population_after(initial: &[u8], cycles: usize) -> u64 {
+    let fish: Vec<_> = initial.iter().copied().map(Lanternfish::from).collect();
+    simulate_array(&fish, cycles)
+}
+
 fn step(mut map: HashMap<Lanternfish, u64>) -> HashMap<Lanternfish, u64> {
     let mut current_fish: Vec<_> = map
         .iter()
@@ -55,6 +164,76 @@ fn step(mut map: HashMap<Lanternfish, u64>) -> HashMap<Lanternfish, u64> {
     map
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulationConfig {
+    /// Timer value for newly spawned fish
+    pub initial_timer: u8,
+    /// Timer value a fish resets to after spawning
+    pub repeat_timer: u8,
+}
+
+pub const DEFAULT_CONFIG: SimulationConfig = SimulationConfig {
+    initial_timer: INITIAL_TIMER,
+    repeat_timer: REPEAT_TIMER,
+};
+
+/// Returns the number of fish (including itself) descended from a single fish
+/// with the given timer after `days` days
+pub fn offspring_at_day(initial_timer: u8, days: usize, config: SimulationConfig) -> u64 {
+    fn inner(
+        timer: u8,
+        days: usize,
+        config: SimulationConfig,
+        memo: &mut HashMap<(u8, usize), u64>,
+    ) -> u64 {
+        // The first spawn happens on the day after the timer reaches 0
+        let spawn_day = timer as usize + 1;
+        if days < spawn_day {
+            return 1;
+        }
+
+        if let Some(&count) = memo.get(&(timer, days)) {
+            return count;
+        }
+
+        let remaining = days - spawn_day;
+        let count = inner(config.repeat_timer, remaining, config, memo)
+            + inner(config.initial_timer, remaining, config, memo);
+        memo.insert((timer, days), count);
+        count
+    }
+
+    inner(initial_timer, days, config, &mut HashMap::new())
+}
+
+/// Returns the number of generations descended from a single fish with the
+/// given timer after `days` days (0 if it has not spawned)
+pub fn offspring_tree_depth(initial_timer: u8, days: usize, config: SimulationConfig) -> usize {
+    fn inner(
+        timer: u8,
+        days: usize,
+        config: SimulationConfig,
+        memo: &mut HashMap<(u8, usize), usize>,
+    ) -> usize {
+        let spawn_day = timer as usize + 1;
+        if days < spawn_day {
+            return 0;
+        }
+
+        if let Some(&depth) = memo.get(&(timer, days)) {
+            return depth;
+        }
+
+        let remaining = days - spawn_day;
+        let depth = inner(config.repeat_timer, remaining, config, memo)
+            .max(1 + inner(config.initial_timer, remaining, config, memo));
+        memo.insert((timer, days), depth);
+        depth
+    }
+
+    inner(initial_timer, days, config, &mut HashMap::new())
+}
+
 fn parse_input(reader: impl BufRead) -> Option<Vec<Lanternfish>> {
     let line = reader.lines().next()?.ok()?;
     let nums: Vec<_> = line
@@ -65,7 +244,7 @@ fn parse_input(reader: impl BufRead) -> Option<Vec<Lanternfish>> {
 }
 
 #[derive(Clone, PartialEq, Eq, Hash)]
-struct Lanternfish(u8);
+pub struct Lanternfish(u8);
 
 impl std::fmt::Debug for Lanternfish {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -82,6 +261,11 @@ impl Lanternfish {
         Lanternfish(timer)
     }
 
+    /// The current timer value
+    pub fn days_until_next_spawn(&self) -> u8 {
+        self.0
+    }
+
     fn age(&mut self) -> Option<Lanternfish> {
         if self.0 == 0 {
             self.0 = REPEAT_TIMER;
@@ -131,4 +315,122 @@ mod tests {
         assert_eq!(simulate(&initial, 18), 26);
         assert_eq!(simulate(&initial, 80), 5934);
     }
+
+    #[test]
+    fn test_simulate_array() {
+        let initial = make_state(&[3, 4, 3, 1, 2]);
+        assert_eq!(simulate_array(&initial, 18), 26);
+        assert_eq!(simulate_array(&initial, 80), 5934);
+        assert_eq!(simulate_array(&initial, 256), 26984457539);
+        assert_eq!(population_after(&[3, 4, 3, 1, 2], 80), 5934);
+        assert_eq!(population_after(&[], 80), 0);
+
+        // Agrees with `simulate` for a spread of inputs
+        let mut seed = 12345u32;
+        for len in 0..20 {
+            let timers: Vec<u8> = (0..len)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                    ((seed >> 16) % (INITIAL_TIMER as u32 + 1)) as u8
+                })
+                .collect();
+            let fish = make_state(&timers);
+            for cycles in [0, 1, 7, 9, 50] {
+                assert_eq!(simulate_array(&fish, cycles), simulate(&fish, cycles));
+            }
+        }
+    }
+
+    #[test]
+    fn test_population_curve() {
+        let initial = make_state(&[3, 4, 3, 1, 2]);
+        assert_eq!(initial[0].days_until_next_spawn(), 3);
+        assert_eq!(Lanternfish::new().days_until_next_spawn(), INITIAL_TIMER);
+
+        let curve = population_curve(&initial, 18);
+        assert_eq!(curve.len(), 19);
+        assert_eq!(curve[0], 5);
+        assert_eq!(curve[18], 26);
+        assert_eq!(&curve[..4], &[5, 5, 6, 7]);
+        assert!(curve.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        let curve = population_curve(&initial, 80);
+        for (days, &population) in curve.iter().enumerate() {
+            assert_eq!(population, simulate_array(&initial, days));
+        }
+        assert_eq!(population_curve(&initial, 0), vec![5]);
+
+        assert_eq!(growth_rate(&initial, 0, 18), 26.0 / 5.0);
+        assert_eq!(growth_rate(&initial, 18, 0), 5.0 / 26.0);
+        assert_eq!(growth_rate(&initial, 7, 7), 1.0);
+    }
+
+    #[test]
+    fn test_simulate_fast() {
+        let initial = make_state(&[3, 4, 3, 1, 2]);
+        assert_eq!(simulate_fast(&initial, 0), 5);
+        assert_eq!(simulate_fast(&initial, 18), 26);
+        assert_eq!(simulate_fast(&initial, 80), 5934);
+        assert_eq!(simulate_fast(&initial, 256), 26984457539);
+        assert_eq!(simulate_fast(&[], 256), 0);
+
+        let mut seed = 54321u32;
+        for len in 0..20 {
+            let timers: Vec<u8> = (0..len)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                    ((seed >> 16) % (INITIAL_TIMER as u32 + 1)) as u8
+                })
+                .collect();
+            let fish = make_state(&timers);
+            // Beyond this the population no longer fits in a u64
+            for cycles in (0..=400).step_by(7) {
+                assert_eq!(
+                    simulate_fast(&fish, cycles as u64),
+                    simulate_array(&fish, cycles)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_offspring_at_day() {
+        assert_eq!(offspring_at_day(3, 3, DEFAULT_CONFIG), 1);
+        assert_eq!(offspring_at_day(3, 4, DEFAULT_CONFIG), 2);
+
+        let single = make_state(&[3]);
+        for days in [18, 80, 256] {
+            assert_eq!(
+                offspring_at_day(3, days, DEFAULT_CONFIG),
+                simulate(&single, days)
+            );
+        }
+
+        let total: u64 = [3, 4, 3, 1, 2]
+            .iter()
+            .map(|&timer| offspring_at_day(timer, 18, DEFAULT_CONFIG))
+            .sum();
+        assert_eq!(total, 26);
+
+        // Fish which reset to 0 spawn every day
+        let config = SimulationConfig {
+            initial_timer: 0,
+            repeat_timer: 0,
+        };
+        assert_eq!(offspring_at_day(0, 5, config), 32);
+    }
+
+    #[test]
+    fn test_offspring_tree_depth() {
+        assert_eq!(offspring_tree_depth(3, 3, DEFAULT_CONFIG), 0);
+        assert_eq!(offspring_tree_depth(3, 4, DEFAULT_CONFIG), 1);
+        // Child spawned on day 4 has its own child on day 13
+        assert_eq!(offspring_tree_depth(3, 12, DEFAULT_CONFIG), 1);
+        assert_eq!(offspring_tree_depth(3, 13, DEFAULT_CONFIG), 2);
+        assert_eq!(offspring_tree_depth(3, 18, DEFAULT_CONFIG), 2);
+
+        // A new generation can be no faster than every 9 days
+        let depth = offspring_tree_depth(3, 256, DEFAULT_CONFIG);
+        assert!(depth > 1 && depth <= 256 / 9 + 1);
+    }
 }
@@ -16,7 +16,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         best, fuel
     );
 
-    let (best, fuel) = best_destination::<true>(&positions).ok_or("No positions given")?;
+    #[cfg(feature = "parallel")]
+    let best = best_destination_parallel::<true>(&positions);
+    #[cfg(not(feature = "parallel"))]
+    let best = best_destination::<true>(&positions);
+    let (best, fuel) = best.ok_or("No positions given")?;
     println!(
         "(Quadratic) Best position is {} with fuel usage {}",
         best, fuel
@@ -35,31 +39,120 @@ fn parse_input(reader: impl BufRead) -> Option<Vec<u32>> {
 }
 
 fn best_destination<const QUADRATIC: bool>(positions: &[u32]) -> Option<(u32, u64)> {
+    best_destination_fn(positions, fuel_cost::<QUADRATIC>)
+}
+
+/// Checks every destination between the smallest and largest position, where
+/// `cost_fn` gives the fuel used to travel a given distance
+pub fn best_destination_fn<F: Fn(u64) -> u64>(positions: &[u32], cost_fn: F) -> Option<(u32, u64)> {
     let min = *positions.iter().min()?;
     let max = *positions.iter().max()?;
     let result = (min..=max)
-        .map(|dst| (dst, cost_for_destination::<QUADRATIC>(positions, dst)))
+        .map(|dst| (dst, cost_for_destination_fn(positions, dst, &cost_fn)))
         .min_by_key(|(_, cost)| *cost)?;
     Some(result)
 }
 
+/// Equivalent to `best_destination`, but checks destinations across multiple
+/// threads.
+///
+/// NOTE: with 1000 positions, the sequential version is faster for ranges of
+/// fewer than roughly 1000 destinations, as there is too little work per
+/// thread to outweigh the cost of splitting it up. Above that, the speedup
+/// depends on the number of cores (see `bench_best_destination_parallel`)
+#[cfg(feature = "parallel")]
+pub fn best_destination_parallel<const QUADRATIC: bool>(positions: &[u32]) -> Option<(u32, u64)> {
+    use rayon::prelude::*;
+
+    let min = *positions.iter().min()?;
+    let max = *positions.iter().max()?;
+    (min..=max)
+        .into_par_iter()
+        .map(|dst| (dst, cost_for_destination::<QUADRATIC>(positions, dst)))
+        // Ties go to the smallest destination, as in `best_destination`
+        .min_by_key(|&(dst, cost)| (cost, dst))
+}
+
+/// Equivalent to `best_destination`, but relies on the cost being convex to
+/// ternary search the range of positions rather than checking every one
+pub fn best_destination_ternary<const QUADRATIC: bool>(positions: &[u32]) -> Option<(u32, u64)> {
+    let mut lo = *positions.iter().min()?;
+    let mut hi = *positions.iter().max()?;
+    while hi - lo > 2 {
+        let third = (hi - lo) / 3;
+        let (m1, m2) = (lo + third, hi - third);
+        if cost_for_destination::<QUADRATIC>(positions, m1)
+            <= cost_for_destination::<QUADRATIC>(positions, m2)
+        {
+            hi = m2;
+        } else {
+            lo = m1;
+        }
+    }
+
+    (lo..=hi)
+        .map(|dst| (dst, cost_for_destination::<QUADRATIC>(positions, dst)))
+        .min_by_key(|(_, cost)| *cost)
+}
+
+/// The median minimises the sum of distances, so is a best destination for
+/// linear fuel costs. Panics if `positions` is empty
+pub fn median_position(positions: &[u32]) -> u32 {
+    let mut sorted = positions.to_vec();
+    sorted.sort_unstable();
+    sorted[(sorted.len() - 1) / 2]
+}
+
 fn cost_for_destination<const QUADRATIC: bool>(positions: &[u32], destination: u32) -> u64 {
+    cost_for_destination_fn(positions, destination, fuel_cost::<QUADRATIC>)
+}
+
+/// Total fuel for all positions to reach `destination`, where `cost_fn` gives
+/// the fuel used to travel a given distance
+pub fn cost_for_destination_fn<F: Fn(u64) -> u64>(
+    positions: &[u32],
+    destination: u32,
+    cost_fn: F,
+) -> u64 {
+    positions
+        .iter()
+        .map(|&p| cost_fn((p as i64 - destination as i64).unsigned_abs()))
+        .sum()
+}
+
+fn fuel_cost<const QUADRATIC: bool>(dist: u64) -> u64 {
     if QUADRATIC {
-        positions
-            .iter()
-            .map(|&p| {
-                let dist = (p as i64 - destination as i64).abs() as u64;
-                dist * (dist + 1) / 2
-            })
-            .sum()
+        dist * (dist + 1) / 2
     } else {
-        positions
-            .iter()
-            .map(|&p| (p as i64 - destination as i64).abs() as u64)
-            .sum()
+        dist
     }
 }
 
+/// Groups positions into clusters, where consecutive (sorted) positions in a
+/// cluster are at most `threshold` apart
+pub fn position_clusters(positions: &[u32], threshold: u32) -> Vec<Vec<u32>> {
+    let mut sorted = positions.to_vec();
+    sorted.sort_unstable();
+
+    let mut clusters: Vec<Vec<u32>> = Vec::new();
+    for p in sorted {
+        match clusters.last_mut() {
+            Some(cluster) if p - cluster[cluster.len() - 1] <= threshold => cluster.push(p),
+            _ => clusters.push(vec![p]),
+        }
+    }
+    clusters
+}
+
+/// Finds the best meeting point for a cluster, where `cost` gives the fuel
+/// used to travel a given distance
+pub fn cluster_optimal_destination(
+    cluster: &[u32],
+    cost: impl Fn(u64) -> u64,
+) -> Option<(u32, u64)> {
+    best_destination_fn(cluster, cost)
+}
+
 #[cfg(test)]
 mod tests {
     use std::io;
@@ -95,6 +188,173 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_best_destination_fn() {
+        let linear = |dist| dist;
+        let quadratic = |dist| dist * (dist + 1) / 2;
+        assert_eq!(best_destination_fn(TEST_POSITIONS, linear), Some((2, 37)));
+        assert_eq!(
+            best_destination_fn(TEST_POSITIONS, quadratic),
+            Some((5, 168))
+        );
+        assert_eq!(best_destination_fn(&[], linear), None);
+
+        for dst in 0..20 {
+            assert_eq!(
+                cost_for_destination_fn(TEST_POSITIONS, dst, linear),
+                cost_for_destination::<false>(TEST_POSITIONS, dst)
+            );
+            assert_eq!(
+                cost_for_destination_fn(TEST_POSITIONS, dst, quadratic),
+                cost_for_destination::<true>(TEST_POSITIONS, dst)
+            );
+        }
+
+        // Constant cost per crab that moves at all
+        let result = best_destination_fn(TEST_POSITIONS, |dist| (dist > 0) as u64);
+        assert_eq!(result, Some((2, 7)));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_best_destination_parallel() {
+        assert_eq!(
+            best_destination_parallel::<false>(TEST_POSITIONS),
+            Some((2, 37))
+        );
+        assert_eq!(
+            best_destination_parallel::<true>(TEST_POSITIONS),
+            Some((5, 168))
+        );
+        assert_eq!(best_destination_parallel::<true>(&[]), None);
+
+        // Every destination between the two costs the same
+        assert_eq!(
+            best_destination_parallel::<false>(&[0, 100]),
+            Some((0, 100))
+        );
+
+        for len in [1, 2, 10, 500] {
+            let positions = random_positions(len, 2000);
+            assert_eq!(
+                best_destination_parallel::<false>(&positions),
+                best_destination::<false>(&positions)
+            );
+            assert_eq!(
+                best_destination_parallel::<true>(&positions),
+                best_destination::<true>(&positions)
+            );
+        }
+    }
+
+    /// Run with `cargo test --release --features parallel --bin day7 --
+    /// --ignored --nocapture`
+    #[cfg(feature = "parallel")]
+    #[test]
+    #[ignore]
+    fn bench_best_destination_parallel() {
+        use std::time::Instant;
+
+        for max in [100, 1000, 10_000, 100_000, 1_000_000] {
+            let positions = random_positions(1000, max);
+
+            let start = Instant::now();
+            let sequential = best_destination::<true>(&positions);
+            let sequential_time = start.elapsed();
+
+            let start = Instant::now();
+            let parallel = best_destination_parallel::<true>(&positions);
+            let parallel_time = start.elapsed();
+
+            assert_eq!(sequential, parallel);
+            println!(
+                "0..{}: sequential {:?}, parallel {:?}",
+                max, sequential_time, parallel_time
+            );
+        }
+    }
+
+    /// `len` positions spread over `0..max`, from a fixed seed
+    fn random_positions(len: usize, max: u32) -> Vec<u32> {
+        let mut seed = 987u32;
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                (seed >> 8) % max
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_best_destination_ternary() {
+        let result = best_destination_ternary::<false>(TEST_POSITIONS);
+        assert_eq!(result, Some((2, 37)));
+        let result = best_destination_ternary::<true>(TEST_POSITIONS);
+        assert_eq!(result, Some((5, 168)));
+        assert_eq!(best_destination_ternary::<true>(&[]), None);
+        assert_eq!(best_destination_ternary::<true>(&[3]), Some((3, 0)));
+
+        for len in [1, 2, 3, 10, 500] {
+            let positions = random_positions(len, 2000);
+            assert_eq!(
+                best_destination_ternary::<false>(&positions),
+                best_destination::<false>(&positions)
+            );
+            assert_eq!(
+                best_destination_ternary::<true>(&positions),
+                best_destination::<true>(&positions)
+            );
+
+            let median = median_position(&positions);
+            let (_, fuel) = best_destination::<false>(&positions).unwrap();
+            assert_eq!(cost_for_destination::<false>(&positions, median), fuel);
+        }
+    }
+
+    #[test]
+    fn test_median_position() {
+        assert_eq!(median_position(TEST_POSITIONS), 2);
+        assert_eq!(median_position(&[5]), 5);
+        assert_eq!(median_position(&[9, 1]), 1);
+    }
+
+    #[test]
+    fn test_position_clusters() {
+        let clusters = position_clusters(TEST_POSITIONS, 2);
+        assert_eq!(
+            clusters,
+            vec![vec![0, 1, 1, 2, 2, 2, 4], vec![7], vec![14, 16]]
+        );
+
+        assert_eq!(position_clusters(TEST_POSITIONS, 100).len(), 1);
+        assert_eq!(position_clusters(&[], 2), Vec::<Vec<u32>>::new());
+    }
+
+    #[test]
+    fn test_cluster_optimal_destination() {
+        let linear = |dist| dist;
+        let clusters = position_clusters(TEST_POSITIONS, 2);
+
+        let mut total = 0;
+        for cluster in &clusters {
+            let (dst, fuel) = cluster_optimal_destination(cluster, linear).unwrap();
+            assert!(cluster[0] <= dst && dst <= cluster[cluster.len() - 1]);
+            total += fuel;
+        }
+        // 6 for the first cluster, 0 for the second and 2 for the third
+        assert_eq!(total, 8);
+
+        // Meeting separately never costs more than meeting all together
+        let (_, global) = best_destination::<false>(TEST_POSITIONS).unwrap();
+        assert!(total <= global);
+
+        let quadratic = |dist| dist * (dist + 1) / 2;
+        let result = cluster_optimal_destination(TEST_POSITIONS, quadratic);
+        assert_eq!(result, Some((5, 168)));
+
+        assert_eq!(cluster_optimal_destination(&[], linear), None);
+    }
+
     mod test_cost_for_destination {
         use super::*;
 
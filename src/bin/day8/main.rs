@@ -1,7 +1,8 @@
 use std::{
     collections::HashMap,
     convert::{TryFrom, TryInto},
-    fs,
+    fmt, fs,
+    io::{self, BufRead},
 };
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -24,7 +25,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
-enum Segment {
+pub enum Segment {
     A = 0,
     B,
     C,
@@ -35,6 +36,16 @@ enum Segment {
 }
 
 impl Segment {
+    const ALL: [Segment; 7] = [
+        Segment::A,
+        Segment::B,
+        Segment::C,
+        Segment::D,
+        Segment::E,
+        Segment::F,
+        Segment::G,
+    ];
+
     fn try_many_from_str(s: &str) -> Option<Vec<Segment>> {
         s.chars()
             .map(|c| c.try_into())
@@ -77,7 +88,53 @@ impl From<Segment> for char {
     }
 }
 
-struct Input {
+/// A set of segments, where bit `n` is set if the set contains the segment
+/// with discriminant `n`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SegmentSet(u8);
+
+impl SegmentSet {
+    pub fn contains(self, segment: Segment) -> bool {
+        self.0 & (1 << segment as u8) != 0
+    }
+
+    pub fn intersection(self, other: SegmentSet) -> SegmentSet {
+        SegmentSet(self.0 & other.0)
+    }
+
+    pub fn union(self, other: SegmentSet) -> SegmentSet {
+        SegmentSet(self.0 | other.0)
+    }
+
+    /// Segments in `self` but not in `other`
+    pub fn difference(self, other: SegmentSet) -> SegmentSet {
+        SegmentSet(self.0 & !other.0)
+    }
+
+    /// Number of segments in the set
+    pub fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    /// Converts each pattern of an entry's digits, or returns `None` if there
+    /// are not exactly `DIGITS_PER_ENTRY` patterns
+    fn from_digits(digits: &[Vec<Segment>]) -> Option<[SegmentSet; DIGITS_PER_ENTRY]> {
+        let sets: Vec<_> = digits.iter().map(|digit| Self::from(&digit[..])).collect();
+        sets.try_into().ok()
+    }
+}
+
+impl From<&[Segment]> for SegmentSet {
+    fn from(segments: &[Segment]) -> Self {
+        SegmentSet(
+            segments
+                .iter()
+                .fold(0, |bits, &segment| bits | (1 << segment as u8)),
+        )
+    }
+}
+
+pub struct Input {
     digits: Vec<Vec<Segment>>,
     outputs: Vec<Vec<Segment>>,
     entry_count: usize,
@@ -86,33 +143,95 @@ struct Input {
 const DIGITS_PER_ENTRY: usize = 10;
 const OUTPUTS_PER_ENTRY: usize = 4;
 
+#[derive(Debug)]
+pub enum Day8Error {
+    Io(io::Error),
+    /// The (1-based) line number which could not be parsed
+    ParseError(usize),
+}
+
+impl fmt::Display for Day8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Day8Error::Io(err) => write!(f, "Failed to read input: {}", err),
+            Day8Error::ParseError(line) => write!(f, "Failed to parse line {}", line),
+        }
+    }
+}
+
+impl std::error::Error for Day8Error {}
+
+impl From<io::Error> for Day8Error {
+    fn from(err: io::Error) -> Self {
+        Day8Error::Io(err)
+    }
+}
+
 impl Input {
     fn from(input: &str) -> Option<Self> {
-        let mut digits = Vec::new();
-        let mut outputs = Vec::new();
-        let mut entry_count = 0;
+        let mut result = Self::empty();
         for line in input.lines() {
-            let (d, o) = line.split_once(" | ")?;
-            digits.extend_from_slice(
-                &d.split(' ')
-                    .map(Segment::try_many_from_str)
-                    .collect::<Option<Vec<_>>>()?,
-            );
-            outputs.extend_from_slice(
-                &o.split(' ')
-                    .map(Segment::try_many_from_str)
-                    .collect::<Option<Vec<_>>>()?,
-            );
-            entry_count += 1;
+            result.push_entry(line)?;
         }
-        Some(Self {
-            digits,
-            outputs,
-            entry_count,
-        })
+        Some(result)
     }
 
-    fn entry_count(&self) -> usize {
+    /// Reads one entry per line
+    pub fn parse_from_reader<R: BufRead>(reader: R) -> Result<Input, Day8Error> {
+        let mut result = Self::empty();
+        for (i, line) in reader.lines().enumerate() {
+            result
+                .push_entry(&line?)
+                .ok_or(Day8Error::ParseError(i + 1))?;
+        }
+        Ok(result)
+    }
+
+    fn empty() -> Self {
+        Self {
+            digits: Vec::new(),
+            outputs: Vec::new(),
+            entry_count: 0,
+        }
+    }
+
+    fn push_entry(&mut self, line: &str) -> Option<()> {
+        let (d, o) = line.split_once(" | ")?;
+        let digits = d
+            .split(' ')
+            .map(Segment::try_many_from_str)
+            .collect::<Option<Vec<_>>>()?;
+        let outputs = o
+            .split(' ')
+            .map(Segment::try_many_from_str)
+            .collect::<Option<Vec<_>>>()?;
+
+        self.digits.extend(digits);
+        self.outputs.extend(outputs);
+        self.entry_count += 1;
+        Some(())
+    }
+
+    /// Splits into the first `entry_index` entries and the remaining entries
+    pub fn split_at(&self, entry_index: usize) -> (Input, Input) {
+        let entry_index = entry_index.min(self.entry_count);
+        let (digits1, digits2) = self.digits.split_at(entry_index * DIGITS_PER_ENTRY);
+        let (outputs1, outputs2) = self.outputs.split_at(entry_index * OUTPUTS_PER_ENTRY);
+        (
+            Self {
+                digits: digits1.to_vec(),
+                outputs: outputs1.to_vec(),
+                entry_count: entry_index,
+            },
+            Self {
+                digits: digits2.to_vec(),
+                outputs: outputs2.to_vec(),
+                entry_count: self.entry_count - entry_index,
+            },
+        )
+    }
+
+    pub fn entry_count(&self) -> usize {
         self.entry_count
     }
 
@@ -144,19 +263,19 @@ fn count_simple_digits(input: &Input) -> usize {
         .count()
 }
 
-fn decode_segments(digits: &[Vec<Segment>]) -> Option<[Segment; 7]> {
-    let one = digits.iter().find(|x| x.len() == 2)?;
-    let seven = digits.iter().find(|x| x.len() == 3)?;
+fn decode_segments(digits: &[SegmentSet; DIGITS_PER_ENTRY]) -> Option<[Segment; 7]> {
+    let one = *digits.iter().find(|x| x.count() == 2)?;
+    let seven = *digits.iter().find(|x| x.count() == 3)?;
 
-    let top_segment = *seven.iter().find(|x| !one.contains(*x))?;
+    let top = seven.difference(one);
+    let top_segment = IntoIterator::into_iter(Segment::ALL).find(|&x| top.contains(x))?;
 
-    use Segment::*;
-    let segment_counts = [A, B, C, D, E, F, G].map(|segment| {
+    let segment_counts = Segment::ALL.map(|segment| {
         (
             segment,
             digits
                 .iter()
-                .filter(|digit| digit.contains(&segment))
+                .filter(|digit| digit.contains(segment))
                 .count(),
         )
     });
@@ -170,18 +289,18 @@ fn decode_segments(digits: &[Vec<Segment>]) -> Option<[Segment; 7]> {
         .0;
 
     // Bottom or middle
-    let four = digits.iter().find(|x| x.len() == 4)?;
+    let four = *digits.iter().find(|x| x.count() == 4)?;
     // Four does not use the bottom segment
     let bottom_segment = segment_counts
         .iter()
         .filter(|x| x.1 == 7)
         .map(|x| x.0)
-        .find(|x| !four.contains(x))?;
+        .find(|&x| !four.contains(x))?;
     let middle_segment = segment_counts
         .iter()
         .filter(|x| x.1 == 7)
         .map(|x| x.0)
-        .find(|x| four.contains(x))?;
+        .find(|&x| four.contains(x))?;
 
     Some([
         top_segment,
@@ -194,7 +313,31 @@ fn decode_segments(digits: &[Vec<Segment>]) -> Option<[Segment; 7]> {
     ])
 }
 
-struct OutputDecoder {
+/// Computes the bitmask of a pattern, where bit `n` is set if the pattern
+/// contains `segments[n]`
+fn pattern_to_bitmask(segments: &[Segment; 7], pattern: &[Segment]) -> u8 {
+    segments
+        .iter()
+        .enumerate()
+        .map(|(idx, segment)| {
+            if pattern.contains(segment) {
+                1u8 << idx
+            } else {
+                0
+            }
+        })
+        .sum()
+}
+
+/// The decoded output value of a single entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeResult {
+    /// `None` if the entry could not be decoded
+    pub value: Option<u64>,
+    pub entry_index: usize,
+}
+
+pub struct OutputDecoder {
     /// Maps patterns to digits
     lookup: HashMap<u8, u8>,
 }
@@ -217,30 +360,28 @@ impl OutputDecoder {
     }
 
     fn decode_all(&self, input: &Input) -> Option<Vec<u64>> {
-        (0..input.entry_count())
-            .map(|idx| self.decode_outputs(input.digits(idx), input.outputs(idx)))
+        self.decode_all_detailed(input)
+            .into_iter()
+            .map(|result| result.value)
             .collect::<Option<Vec<_>>>()
     }
 
+    /// Like `decode_all`, but keeps going past entries which fail to decode
+    fn decode_all_detailed(&self, input: &Input) -> Vec<DecodeResult> {
+        (0..input.entry_count())
+            .map(|entry_index| DecodeResult {
+                value: self.decode_outputs(input.digits(entry_index), input.outputs(entry_index)),
+                entry_index,
+            })
+            .collect()
+    }
+
     fn decode_outputs(&self, digits: &[Vec<Segment>], outputs: &[Vec<Segment>]) -> Option<u64> {
-        let segments = decode_segments(digits)?;
+        let segments = decode_segments(&SegmentSet::from_digits(digits)?)?;
 
         let result_digits = outputs
             .iter()
-            .map(|output| {
-                let pattern = segments
-                    .iter()
-                    .enumerate()
-                    .map(|(idx, segment)| {
-                        if output.contains(segment) {
-                            1u8 << idx
-                        } else {
-                            0
-                        }
-                    })
-                    .sum::<u8>();
-                Some(*self.lookup.get(&pattern)? as u64)
-            })
+            .map(|output| Some(self.decode_single_pattern(&segments, output)? as u64))
             .collect::<Option<Vec<_>>>()?;
 
         Some(
@@ -250,6 +391,109 @@ impl OutputDecoder {
                 + result_digits[3],
         )
     }
+
+    /// Decodes a single output pattern using the ten unique patterns in
+    /// `digits`
+    pub fn decode_digit(&self, digits: &[Vec<Segment>], output: &[Segment]) -> Option<u8> {
+        let segments = decode_segments(&SegmentSet::from_digits(digits)?)?;
+        self.decode_single_pattern(&segments, output)
+    }
+
+    /// Maps a single pattern to its digit, given the segment mapping produced
+    /// by `decode_segments`
+    fn decode_single_pattern(&self, segments: &[Segment; 7], pattern: &[Segment]) -> Option<u8> {
+        self.lookup
+            .get(&pattern_to_bitmask(segments, pattern))
+            .copied()
+    }
+}
+
+/// Splits the entries into `folds` groups of (nearly) equal size
+fn fold_groups(input: &Input, folds: usize) -> Vec<Input> {
+    (0..folds)
+        .map(|fold| {
+            let start = fold * input.entry_count() / folds;
+            let end = (fold + 1) * input.entry_count() / folds;
+            let (_, rest) = input.split_at(start);
+            rest.split_at(end - start).0
+        })
+        .collect()
+}
+
+/// Partitions the entries into `folds` groups and decodes each held out group
+/// in turn, returning the fraction of outputs which are decoded correctly.
+/// The input carries no answers, so an output counts as correct if its entry's
+/// ten patterns decode to each of the digits exactly once (which pins down
+/// the wiring) and the output decodes under that wiring. Returns 0 if `folds`
+/// is zero or there are no outputs.
+///
+/// NOTE: the decoder has no state learned from other entries (each entry is
+/// decoded independently), so training on the remaining groups is a no-op
+pub fn cross_validate_decoder(input: &Input, folds: usize) -> f64 {
+    let decoder = OutputDecoder::new();
+    let mut total = 0;
+    let mut correct = 0;
+    for held_out in fold_groups(input, folds) {
+        for idx in 0..held_out.entry_count() {
+            let outputs = held_out.outputs(idx);
+            total += outputs.len();
+            let segments = match SegmentSet::from_digits(held_out.digits(idx))
+                .and_then(|digits| decode_segments(&digits))
+            {
+                Some(segments) => segments,
+                None => continue,
+            };
+
+            let mut pattern_digits = held_out
+                .digits(idx)
+                .iter()
+                .map(|pattern| decoder.decode_single_pattern(&segments, pattern))
+                .collect::<Option<Vec<_>>>()
+                .unwrap_or_default();
+            pattern_digits.sort_unstable();
+            if !pattern_digits.iter().copied().eq(0..DIGITS_PER_ENTRY as u8) {
+                continue;
+            }
+
+            correct += outputs
+                .iter()
+                .filter(|output| decoder.decode_single_pattern(&segments, output).is_some())
+                .count();
+        }
+    }
+
+    if total == 0 {
+        return 0.;
+    }
+    correct as f64 / total as f64
+}
+
+/// Like `cross_validate_decoder`, but gives the decode rate: the fraction of
+/// outputs which decode to some digit, without checking the entry's other
+/// patterns. Returns `None` if `folds` is zero or there are no outputs
+pub fn cross_validate_decode_rate(input: &Input, folds: usize) -> Option<f64> {
+    let decoder = OutputDecoder::new();
+    let mut total = 0;
+    let mut decoded = 0;
+    for held_out in fold_groups(input, folds) {
+        for idx in 0..held_out.entry_count() {
+            let outputs = held_out.outputs(idx);
+            total += outputs.len();
+            let segments = SegmentSet::from_digits(held_out.digits(idx))
+                .and_then(|digits| decode_segments(&digits));
+            if let Some(segments) = segments {
+                decoded += outputs
+                    .iter()
+                    .filter(|output| decoder.decode_single_pattern(&segments, output).is_some())
+                    .count();
+            }
+        }
+    }
+
+    if total == 0 {
+        return None;
+    }
+    Some(decoded as f64 / total as f64)
 }
 
 #[cfg(test)]
@@ -285,6 +529,87 @@ gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce
         assert_eq!(input.outputs(3), expected);
     }
 
+    #[test]
+    fn test_parse_from_reader() {
+        let input = Input::parse_from_reader(io::Cursor::new(TEST_INPUT)).unwrap();
+        let expected = Input::from(TEST_INPUT).unwrap();
+        assert_eq!(input.entry_count(), 10);
+        assert_eq!(input.digits, expected.digits);
+        assert_eq!(input.outputs, expected.outputs);
+
+        let invalid = TEST_INPUT.replacen("fdcge", "fdcxe", 1);
+        let result = Input::parse_from_reader(io::Cursor::new(invalid));
+        assert!(matches!(result, Err(Day8Error::ParseError(1))));
+
+        let invalid = format!("{}not valid\n", TEST_INPUT);
+        let result = Input::parse_from_reader(io::Cursor::new(invalid));
+        assert!(matches!(result, Err(Day8Error::ParseError(11))));
+
+        // Invalid UTF-8
+        let result = Input::parse_from_reader(io::Cursor::new(b"ab | \xff\n"));
+        assert!(matches!(result, Err(Day8Error::Io(_))));
+
+        let input = Input::parse_from_reader(io::Cursor::new("")).unwrap();
+        assert_eq!(input.entry_count(), 0);
+    }
+
+    #[test]
+    fn test_split_at() {
+        let input = Input::from(TEST_INPUT).unwrap();
+
+        let (first, second) = input.split_at(3);
+        assert_eq!(first.entry_count(), 3);
+        assert_eq!(second.entry_count(), 7);
+        assert_eq!(first.digits(2), input.digits(2));
+        assert_eq!(second.digits(0), input.digits(3));
+        assert_eq!(second.outputs(6), input.outputs(9));
+
+        let (first, second) = input.split_at(20);
+        assert_eq!(first.entry_count(), 10);
+        assert_eq!(second.entry_count(), 0);
+        assert!(second.all_outputs().is_empty());
+    }
+
+    #[test]
+    fn test_cross_validate_decoder() {
+        let input = Input::from(TEST_INPUT).unwrap();
+        assert_eq!(cross_validate_decoder(&input, 5), 1.0);
+        assert_eq!(cross_validate_decoder(&input, 3), 1.0);
+        assert_eq!(cross_validate_decoder(&input, 0), 0.0);
+
+        // Decoding each held out group gives the known answers
+        let decoder = OutputDecoder::new();
+        let decoded: Vec<u64> = fold_groups(&input, 5)
+            .iter()
+            .flat_map(|held_out| decoder.decode_all(held_out).unwrap())
+            .collect();
+        assert_eq!(
+            decoded,
+            [8394, 9781, 1197, 9361, 4873, 8418, 4548, 1625, 8717, 4315]
+        );
+
+        let bad =
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb abc | cdfeb fcadb cdfeb cdbaf";
+        let input = Input::from(&format!("{}{}", TEST_INPUT, bad)).unwrap();
+        assert_eq!(cross_validate_decoder(&input, 5), 40. / 44.);
+    }
+
+    #[test]
+    fn test_cross_validate_decode_rate() {
+        let input = Input::from(TEST_INPUT).unwrap();
+        assert_eq!(cross_validate_decode_rate(&input, 5), Some(1.0));
+        assert_eq!(cross_validate_decode_rate(&input, 3), Some(1.0));
+        assert_eq!(cross_validate_decode_rate(&input, 0), None);
+        let (_, empty) = input.split_at(10);
+        assert_eq!(cross_validate_decode_rate(&empty, 2), None);
+
+        // An undecodable entry (no pattern for a one)
+        let bad =
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb abc | cdfeb fcadb cdfeb cdbaf";
+        let input = Input::from(&format!("{}{}", TEST_INPUT, bad)).unwrap();
+        assert_eq!(cross_validate_decode_rate(&input, 5), Some(40. / 44.));
+    }
+
     #[test]
     fn test_count_simple_digits() {
         let input = Input::from(TEST_INPUT).unwrap();
@@ -306,4 +631,104 @@ gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce
         let actual = decoder.decode_all(&input).unwrap();
         assert_eq!(&actual, &expected);
     }
+
+    #[test]
+    fn test_decode_all_detailed() {
+        let decoder = OutputDecoder::new();
+        let input = Input::from(TEST_INPUT).unwrap();
+        let results = decoder.decode_all_detailed(&input);
+        assert_eq!(results.len(), 10);
+        assert!(results.iter().all(|result| result.value.is_some()));
+        assert_eq!(
+            results[3],
+            DecodeResult {
+                value: Some(9361),
+                entry_index: 3
+            }
+        );
+
+        let bad =
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb abc | cdfeb fcadb cdfeb cdbaf";
+        let input = Input::from(&format!("{}{}", TEST_INPUT, bad)).unwrap();
+        let results = decoder.decode_all_detailed(&input);
+        assert_eq!(results[10].value, None);
+        assert_eq!(results[10].entry_index, 10);
+        assert!(decoder.decode_all(&input).is_none());
+    }
+
+    #[test]
+    fn test_decode_digit() {
+        let input =
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf";
+        let input = Input::from(input).unwrap();
+
+        let decoder = OutputDecoder::new();
+        for (output, digit) in input.outputs(0).iter().zip([5, 3, 5, 3]) {
+            assert_eq!(decoder.decode_digit(input.digits(0), output), Some(digit));
+        }
+        let pattern = Segment::try_many_from_str("ab").unwrap();
+        assert_eq!(decoder.decode_digit(input.digits(0), &pattern), Some(1));
+        assert_eq!(decoder.decode_digit(&[], &pattern), None);
+    }
+
+    #[test]
+    fn test_decode_single_pattern() {
+        let input =
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf";
+        let input = Input::from(input).unwrap();
+        let digits = SegmentSet::from_digits(input.digits(0)).unwrap();
+        let segments = decode_segments(&digits).unwrap();
+
+        let decoder = OutputDecoder::new();
+        let expected = [8, 5, 2, 3, 7, 9, 6, 4, 0, 1];
+        for (pattern, digit) in input.digits(0).iter().zip(expected) {
+            assert_eq!(
+                decoder.decode_single_pattern(&segments, pattern),
+                Some(digit)
+            );
+        }
+
+        // Wrong number of segments
+        let pattern = Segment::try_many_from_str("abcde").unwrap();
+        assert_eq!(decoder.decode_single_pattern(&segments, &pattern), None);
+        assert_eq!(decoder.decode_single_pattern(&segments, &[]), None);
+    }
+
+    #[test]
+    fn test_segment_set() {
+        use Segment::*;
+
+        let one = SegmentSet::from(&[A, B][..]);
+        let seven = SegmentSet::from(&[D, A, B][..]);
+        assert_eq!(one.count(), 2);
+        assert_eq!(seven.count(), 3);
+        assert_eq!(one.intersection(seven).count(), 2);
+        assert_eq!(one.intersection(seven), one);
+        assert_eq!(seven.difference(one), SegmentSet::from(&[D][..]));
+        assert_eq!(one.difference(seven), SegmentSet::default());
+        assert_eq!(one.union(SegmentSet::from(&[G][..])).count(), 3);
+        assert!(seven.contains(D));
+        assert!(!one.contains(D));
+        assert_eq!(SegmentSet::from(&[][..]).count(), 0);
+
+        let input = Input::from(TEST_INPUT).unwrap();
+        let digits = SegmentSet::from_digits(input.digits(0)).unwrap();
+        let one = digits.iter().find(|x| x.count() == 2).unwrap();
+        let seven = digits.iter().find(|x| x.count() == 3).unwrap();
+        assert_eq!(one.intersection(*seven).count(), 2);
+        assert!(SegmentSet::from_digits(&input.digits(0)[..9]).is_none());
+    }
+
+    #[test]
+    fn test_pattern_to_bitmask() {
+        use Segment::*;
+        let segments = [A, B, C, D, E, F, G];
+        assert_eq!(pattern_to_bitmask(&segments, &[]), 0);
+        assert_eq!(pattern_to_bitmask(&segments, &[B, C]), 0b00000110);
+        assert_eq!(pattern_to_bitmask(&segments, &[G, A]), 0b01000001);
+
+        let segments = [G, F, E, D, C, B, A];
+        assert_eq!(pattern_to_bitmask(&segments, &[G, A]), 0b01000001);
+        assert_eq!(pattern_to_bitmask(&segments, &[F]), 0b00000010);
+    }
 }
@@ -1,13 +1,15 @@
+use rust_aoc_2021::util::grid::{parse_digit_grid, Grid};
 use std::{
     collections::{HashMap, HashSet, VecDeque},
     fs,
+    str::FromStr,
 };
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let filename = "src/bin/day9/input.txt";
     let input = fs::read_to_string(filename).map_err(|_| "Could not read input contents")?;
 
-    let map = Map::from_str(&input).ok_or("Could not parse input")?;
+    let map: Map = input.parse()?;
     let risk_level = Map::total_risk_level(map.low_points());
     println!("Sum of risk levels: {}", risk_level);
 
@@ -19,58 +21,98 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 #[derive(Debug)]
-struct Map {
-    width: usize,
-    height: usize,
-    map: Vec<u8>,
+pub struct Map {
+    map: Grid<u8>,
 }
 
-impl Map {
-    pub const MAX_HEIGHT: u8 = 9;
-
-    pub fn from_str(input: &str) -> Option<Self> {
-        let width = input.find('\n')?;
-        if width == 0 {
-            return None;
-        }
+impl FromStr for Map {
+    type Err = &'static str;
 
-        let map = input
-            .chars()
-            .filter(|c| !c.is_whitespace())
-            .map(|c| Some(c.to_digit(10)? as u8))
-            .collect::<Option<Vec<u8>>>()?;
-        let height = map.len() / width;
-        Some(Self { width, height, map })
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (width, height, map) = parse_digit_grid(input).ok_or("Could not parse height map")?;
+        Ok(Self {
+            map: Grid::new(width, height, map),
+        })
     }
+}
+
+impl Map {
+    pub const MAX_HEIGHT: u8 = 9;
 
     pub fn points(&self) -> impl Iterator<Item = ((i32, i32), u8)> + '_ {
-        (0..self.height).flat_map(move |y| {
-            (0..self.width).map(move |x| {
-                (
-                    (x as i32, y as i32),
-                    self.height_at(x as i32, y as i32).unwrap(),
-                )
-            })
-        })
+        self.map
+            .iter_coords()
+            .map(|(x, y, &height)| ((x, y), height))
     }
 
     pub fn low_points(&self) -> impl Iterator<Item = ((i32, i32), u8)> + '_ {
         self.points().filter(move |&((x, y), height)| {
-            let up = self.height_at(x, y - 1).unwrap_or(u8::MAX);
-            let down = self.height_at(x, y + 1).unwrap_or(u8::MAX);
-            let left = self.height_at(x - 1, y).unwrap_or(u8::MAX);
-            let right = self.height_at(x + 1, y).unwrap_or(u8::MAX);
-            height < up && height < down && height < left && height < right
+            self.map
+                .neighbors4(x, y)
+                .all(|(_, _, &neighbor)| height < neighbor)
         })
     }
 
+    pub fn width(&self) -> usize {
+        self.map.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.map.height()
+    }
+
     #[inline]
     pub fn height_at(&self, x: i32, y: i32) -> Option<u8> {
-        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
-            return None;
+        self.map.get(x, y).copied()
+    }
+
+    /// Counts the cells at each height
+    pub fn height_histogram(&self) -> [usize; Self::MAX_HEIGHT as usize + 1] {
+        let mut histogram = [0; Self::MAX_HEIGHT as usize + 1];
+        for &height in self.map.data() {
+            histogram[height as usize] += 1;
+        }
+        histogram
+    }
+
+    /// Returns the height at the given percentile (0 to 100) of all cells,
+    /// using the nearest-rank method
+    pub fn height_at_percentile(&self, pct: f64) -> u8 {
+        let histogram = self.height_histogram();
+        let total = self.map.data().len();
+        let rank = ((pct.clamp(0.0, 100.0) / 100.0 * total as f64).ceil() as usize).max(1);
+
+        let mut seen = 0;
+        for (height, &count) in histogram.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return height as u8;
+            }
         }
+        Self::MAX_HEIGHT
+    }
 
-        self.map.get(y as usize * self.width + x as usize).copied()
+    pub fn mean_height(&self) -> f64 {
+        let data = self.map.data();
+        if data.is_empty() {
+            return 0.0;
+        }
+        data.iter().map(|&height| height as f64).sum::<f64>() / data.len() as f64
+    }
+
+    /// Population standard deviation of the heights
+    pub fn std_height(&self) -> f64 {
+        let data = self.map.data();
+        if data.is_empty() {
+            return 0.0;
+        }
+        let mean = self.mean_height();
+        let variance = data
+            .iter()
+            .map(|&height| (height as f64 - mean).powi(2))
+            .sum::<f64>()
+            / data.len() as f64;
+        variance.sqrt()
     }
 
     pub fn total_risk_level(low_points: impl Iterator<Item = ((i32, i32), u8)>) -> u64 {
@@ -82,6 +124,202 @@ impl Map {
     pub fn risk_level(height: u8) -> u8 {
         height + 1
     }
+
+    /// Counts the cells reachable from `(x, y)` by cardinal moves without
+    /// passing through a cell with height of at least `height_limit`
+    /// (including the starting cell itself)
+    pub fn count_reachable_cells_below(&self, x: i32, y: i32, height_limit: u8) -> usize {
+        match self.height_at(x, y) {
+            Some(height) if height < height_limit => {}
+            _ => return 0,
+        }
+
+        let mut visited = HashSet::from([(x, y)]);
+        let mut queue = VecDeque::from([(x, y)]);
+        while let Some((x, y)) = queue.pop_front() {
+            for (x, y, &height) in self.map.neighbors4(x, y) {
+                if height < height_limit && visited.insert((x, y)) {
+                    queue.push_back((x, y));
+                }
+            }
+        }
+
+        visited.len()
+    }
+
+    /// Returns every path from `start` to `end` which does not revisit cells,
+    /// using only cells with height below `height_limit`
+    pub fn find_all_paths_between(
+        &self,
+        start: (i32, i32),
+        end: (i32, i32),
+        height_limit: u8,
+    ) -> Vec<Vec<(i32, i32)>> {
+        fn inner(
+            map: &Map,
+            end: (i32, i32),
+            height_limit: u8,
+            path: &mut Vec<(i32, i32)>,
+            visited: &mut HashSet<(i32, i32)>,
+            paths: &mut Vec<Vec<(i32, i32)>>,
+        ) {
+            let (x, y) = path[path.len() - 1];
+            if (x, y) == end {
+                paths.push(path.clone());
+                return;
+            }
+
+            for (x, y, &height) in map.map.neighbors4(x, y) {
+                let next = (x, y);
+                if height < height_limit && visited.insert(next) {
+                    path.push(next);
+                    inner(map, end, height_limit, path, visited, paths);
+                    path.pop();
+                    visited.remove(&next);
+                }
+            }
+        }
+
+        let mut paths = Vec::new();
+        if self.is_passable(start, height_limit) {
+            let mut path = vec![start];
+            let mut visited = HashSet::from([start]);
+            inner(self, end, height_limit, &mut path, &mut visited, &mut paths);
+        }
+        paths
+    }
+
+    /// Returns a shortest path from `start` to `end`, using only cells with
+    /// height below `height_limit`
+    pub fn shortest_path_between(
+        &self,
+        start: (i32, i32),
+        end: (i32, i32),
+        height_limit: u8,
+    ) -> Option<Vec<(i32, i32)>> {
+        if !self.is_passable(start, height_limit) {
+            return None;
+        }
+
+        let mut parents = HashMap::from([(start, start)]);
+        let mut queue = VecDeque::from([start]);
+        while let Some((x, y)) = queue.pop_front() {
+            if (x, y) == end {
+                let mut path = vec![end];
+                let mut current = end;
+                while current != start {
+                    current = parents[&current];
+                    path.push(current);
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for (nx, ny, &height) in self.map.neighbors4(x, y) {
+                let next = (nx, ny);
+                if height < height_limit && !parents.contains_key(&next) {
+                    parents.insert(next, (x, y));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Direction of the lowest of the (up to four) neighbors, if it is lower
+    /// than the point itself. Gives `(0, 0)` if no neighbor is lower, or `None`
+    /// if the point is out of bounds or a wall. Ties are broken in the order
+    /// left, right, up, down
+    pub fn gradient_direction(&self, x: i32, y: i32) -> Option<(i32, i32)> {
+        let height = self.height_at(x, y)?;
+        if height == Self::MAX_HEIGHT {
+            return None;
+        }
+
+        let lowest = self
+            .map
+            .neighbors4(x, y)
+            .min_by_key(|&(_, _, &neighbor)| neighbor);
+        Some(match lowest {
+            Some((nx, ny, &neighbor)) if neighbor < height => (nx - x, ny - y),
+            _ => (0, 0),
+        })
+    }
+
+    /// Assigns each point (indexed by row, then column) to a basin by following
+    /// the gradient down to a low point. Basins are numbered in the order of
+    /// `low_points`. Walls, and points whose descent stops somewhere other than
+    /// a low point, are given `None`
+    pub fn flow_basin_id(&self) -> Vec<Vec<Option<BasinId>>> {
+        let low_points: HashMap<_, BasinId> = self
+            .low_points()
+            .enumerate()
+            .map(|(id, (point, _))| (point, id))
+            .collect();
+
+        let mut basins = vec![vec![None; self.map.width()]; self.map.height()];
+        for ((x, y), _) in self.points() {
+            let (mut cx, mut cy) = (x, y);
+            while let Some((dx, dy)) = self.gradient_direction(cx, cy) {
+                if (dx, dy) == (0, 0) {
+                    basins[y as usize][x as usize] = low_points.get(&(cx, cy)).copied();
+                    break;
+                }
+                cx += dx;
+                cy += dy;
+            }
+        }
+        basins
+    }
+
+    /// Renders the heights row by row, with walls (cells of maximum height)
+    /// shown as '.'
+    pub fn render(&self) -> String {
+        self.render_cells(|_, _, c| c.to_string())
+    }
+
+    /// Like `render`, but with the cells of each basin colored using ANSI
+    /// escape codes. Low points are shown in bold
+    pub fn render_with_basins(&self, basins: &BasinsResult) -> String {
+        let basin_ids: HashMap<_, _> = basins
+            .all_basin_points()
+            .flat_map(|(id, points)| points.iter().map(move |&point| (point, id)))
+            .collect();
+        let low_points: HashSet<_> = self.low_points().map(|(point, _)| point).collect();
+
+        self.render_cells(|x, y, c| match basin_ids.get(&(x, y)) {
+            Some(&id) => {
+                let weight = if low_points.contains(&(x, y)) {
+                    "1;"
+                } else {
+                    ""
+                };
+                format!("\x1b[{}{}m{}\x1b[0m", weight, 30 + id % 8, c)
+            }
+            None => c.to_string(),
+        })
+    }
+
+    fn render_cells(&self, mut render_cell: impl FnMut(i32, i32, char) -> String) -> String {
+        let mut result = String::new();
+        for (x, y, &height) in self.map.iter_coords() {
+            let c = if height == Self::MAX_HEIGHT {
+                '.'
+            } else {
+                (b'0' + height) as char
+            };
+            result += &render_cell(x, y, c);
+            if x as usize == self.map.width() - 1 {
+                result.push('\n');
+            }
+        }
+        result
+    }
+
+    fn is_passable(&self, (x, y): (i32, i32), height_limit: u8) -> bool {
+        matches!(self.height_at(x, y), Some(height) if height < height_limit)
+    }
 }
 
 type BasinId = usize;
@@ -91,7 +329,7 @@ struct Basins {
     map: Map,
     unvisited: HashSet<(i32, i32)>,
     basin_sizes: HashMap<BasinId, usize>,
-    basin_points: HashSet<(BasinId, i32, i32)>,
+    basin_points: HashMap<BasinId, HashSet<(i32, i32)>>,
 }
 
 impl Basins {
@@ -105,7 +343,7 @@ impl Basins {
             map,
             unvisited,
             basin_sizes: HashMap::new(),
-            basin_points: HashSet::new(),
+            basin_points: HashMap::new(),
         }
     }
 
@@ -118,6 +356,7 @@ impl Basins {
 
         let result = BasinsResult {
             basin_sizes: self.basin_sizes,
+            basin_points: self.basin_points,
         };
         (self.map, result)
     }
@@ -128,32 +367,89 @@ impl Basins {
         self.unvisited.remove(&start);
 
         let mut size = 0;
+        let mut points = HashSet::new();
         while let Some((x, y)) = queue.pop_front() {
-            for &(x, y) in &[(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
-                if let Some(height) = self.map.height_at(x, y) {
-                    if height != Map::MAX_HEIGHT && self.unvisited.contains(&(x, y)) {
-                        queue.push_back((x, y));
-                        self.unvisited.remove(&(x, y));
-                    }
+            for (x, y, &height) in self.map.map.neighbors4(x, y) {
+                if height != Map::MAX_HEIGHT && self.unvisited.remove(&(x, y)) {
+                    queue.push_back((x, y));
                 }
             }
 
             size += 1;
-            self.basin_points.insert((basin_id, x, y));
+            points.insert((x, y));
         }
         self.basin_sizes.insert(basin_id, size);
+        self.basin_points.insert(basin_id, points);
     }
 }
 
 #[derive(Debug)]
-struct BasinsResult {
+pub struct BasinsResult {
     basin_sizes: HashMap<BasinId, usize>,
+    basin_points: HashMap<BasinId, HashSet<(i32, i32)>>,
 }
 
 impl BasinsResult {
     pub fn basin_sizes(&self) -> impl Iterator<Item = usize> + '_ {
         self.basin_sizes.values().copied()
     }
+
+    pub fn basin_points(&self, id: BasinId) -> Option<&HashSet<(i32, i32)>> {
+        self.basin_points.get(&id)
+    }
+
+    pub fn all_basin_points(&self) -> impl Iterator<Item = (BasinId, &HashSet<(i32, i32)>)> + '_ {
+        self.basin_points.iter().map(|(&id, points)| (id, points))
+    }
+
+    /// Merges each basin with fewer than `min_size` points into the largest
+    /// basin it shares an edge with, smallest first. Since sizes are compared
+    /// strictly, single cell basins need a `min_size` of 2 (`1` merges
+    /// nothing). Basins found by flood fill are always separated by walls, so
+    /// this only affects basins found some other way.
+    ///
+    /// NOTE: this is on `BasinsResult` rather than `Basins`, since the basins
+    /// are only complete once `Basins::compute_basins` has consumed it
+    pub fn merge_basins(&mut self, min_size: usize) {
+        let mut owners: HashMap<(i32, i32), BasinId> = self
+            .all_basin_points()
+            .flat_map(|(id, points)| points.iter().map(move |&point| (point, id)))
+            .collect();
+
+        let mut small: Vec<_> = self
+            .basin_sizes
+            .iter()
+            .filter(|&(_, &size)| size < min_size)
+            .map(|(&id, &size)| (size, id))
+            .collect();
+        small.sort_unstable();
+
+        for (_, id) in small {
+            // May have grown from merges since
+            if self.basin_sizes[&id] >= min_size {
+                continue;
+            }
+
+            let target = self.basin_points[&id]
+                .iter()
+                .flat_map(|&(x, y)| [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)])
+                .filter_map(|point| owners.get(&point).copied())
+                .filter(|&other| other != id)
+                .max_by_key(|&other| (self.basin_sizes[&other], std::cmp::Reverse(other)));
+            let target = match target {
+                Some(target) => target,
+                None => continue,
+            };
+
+            let points = self.basin_points.remove(&id).unwrap();
+            self.basin_sizes.remove(&id);
+            for &point in &points {
+                owners.insert(point, target);
+            }
+            *self.basin_sizes.get_mut(&target).unwrap() += points.len();
+            self.basin_points.get_mut(&target).unwrap().extend(points);
+        }
+    }
 }
 
 pub fn largest_basins_product(mut basins: Vec<usize>) -> usize {
@@ -179,9 +475,12 @@ mod tests {
         #[test]
         fn from_str() {
             let map = Map::from_str(TEST_INPUT).unwrap();
-            assert_eq!(map.width, 10);
+            assert_eq!(map.width(), 10);
             assert_eq!(&map.map[0..5], &[2, 1, 9, 9, 9]);
             assert_eq!(&map.map[10..15], &[3, 9, 8, 7, 8]);
+
+            // Rows of different lengths
+            assert!(Map::from_str("12\n345\n").is_err());
         }
 
         #[test]
@@ -191,6 +490,104 @@ mod tests {
             assert_eq!(&low, &[((1, 0), 1), ((9, 0), 0), ((2, 2), 5), ((6, 4), 5)]);
             assert_eq!(Map::total_risk_level(low.iter().copied()), 15);
         }
+
+        #[test]
+        fn height_statistics() {
+            let map = Map::from_str(TEST_INPUT).unwrap();
+            let histogram = map.height_histogram();
+            assert_eq!(histogram.iter().sum::<usize>(), 50);
+            assert_eq!(histogram, [1, 3, 4, 2, 2, 2, 5, 6, 10, 15]);
+
+            assert_eq!(map.height_at_percentile(0.0), 0);
+            assert_eq!(map.height_at_percentile(50.0), 7);
+            assert_eq!(map.height_at_percentile(70.0), 8);
+            assert_eq!(map.height_at_percentile(71.0), 9);
+            assert_eq!(map.height_at_percentile(100.0), 9);
+
+            assert!((map.mean_height() - 6.44).abs() < 1e-9);
+            assert!((map.std_height() - 2.736_128_651_946_03).abs() < 1e-9);
+        }
+
+        #[test]
+        fn count_reachable_cells_below() {
+            let map = Map::from_str(TEST_INPUT).unwrap();
+            let mut reachable: Vec<_> = map
+                .low_points()
+                .map(|((x, y), _)| map.count_reachable_cells_below(x, y, Map::MAX_HEIGHT))
+                .collect();
+            reachable.sort_unstable();
+
+            let (map, result) = Basins::new(map).compute_basins();
+            let mut sizes: Vec<_> = result.basin_sizes().collect();
+            sizes.sort_unstable();
+            assert_eq!(reachable, sizes);
+
+            // Restricting the height shrinks the basins
+            let limited: usize = map
+                .low_points()
+                .map(|((x, y), _)| map.count_reachable_cells_below(x, y, 5))
+                .sum();
+            assert!(limited < sizes.iter().sum());
+            assert_eq!(map.count_reachable_cells_below(1, 0, 5), 3);
+            assert_eq!(map.count_reachable_cells_below(9, 0, 3), 6);
+
+            // Starting cell at or above the limit
+            assert_eq!(map.count_reachable_cells_below(2, 0, Map::MAX_HEIGHT), 0);
+            assert_eq!(map.count_reachable_cells_below(-1, 0, Map::MAX_HEIGHT), 0);
+        }
+    }
+
+    mod paths {
+        use super::*;
+
+        const SMALL_INPUT: &str = "\
+01239
+12349
+23459
+99999
+00000
+";
+
+        #[test]
+        fn find_all_paths_between() {
+            let map = Map::from_str(SMALL_INPUT).unwrap();
+            let paths = map.find_all_paths_between((0, 0), (2, 2), Map::MAX_HEIGHT);
+            assert!(!paths.is_empty());
+            for path in &paths {
+                assert_eq!(path[0], (0, 0));
+                assert_eq!(path[path.len() - 1], (2, 2));
+                assert!(path
+                    .iter()
+                    .all(|&(x, y)| map.height_at(x, y).unwrap() != Map::MAX_HEIGHT));
+
+                let unique: HashSet<_> = path.iter().collect();
+                assert_eq!(unique.len(), path.len());
+            }
+
+            let shortest = map
+                .shortest_path_between((0, 0), (2, 2), Map::MAX_HEIGHT)
+                .unwrap();
+            assert_eq!(shortest.len(), 5);
+            assert_eq!(
+                paths.iter().map(|path| path.len()).min(),
+                Some(shortest.len())
+            );
+
+            // Paths can't cross the row of 9s
+            assert!(map
+                .find_all_paths_between((0, 0), (0, 4), Map::MAX_HEIGHT)
+                .is_empty());
+            assert!(map
+                .shortest_path_between((0, 0), (0, 4), Map::MAX_HEIGHT)
+                .is_none());
+
+            // Only the top left corner is below 2
+            let paths = map.find_all_paths_between((0, 0), (1, 0), 2);
+            assert_eq!(paths, vec![vec![(0, 0), (1, 0)]]);
+            let paths = map.find_all_paths_between((0, 0), (0, 0), 2);
+            assert_eq!(paths, vec![vec![(0, 0)]]);
+            assert!(map.find_all_paths_between((0, 0), (2, 0), 2).is_empty());
+        }
     }
 
     #[test]
@@ -201,4 +598,125 @@ mod tests {
         assert_eq!(sizes.len(), 4);
         assert_eq!(largest_basins_product(sizes), 1134)
     }
+
+    #[test]
+    fn flow_basins() {
+        let map = Map::from_str(TEST_INPUT).unwrap();
+        assert_eq!(map.gradient_direction(1, 0), Some((0, 0)));
+        assert_eq!(map.gradient_direction(0, 0), Some((1, 0)));
+        assert_eq!(map.gradient_direction(0, 1), Some((0, -1)));
+        assert_eq!(map.gradient_direction(2, 0), None);
+        assert_eq!(map.gradient_direction(10, 0), None);
+
+        let basins = map.flow_basin_id();
+        assert_eq!(basins.len(), 5);
+        assert!(basins.iter().all(|row| row.len() == 10));
+        assert_eq!(basins[0][2], None);
+
+        let mut sizes: HashMap<BasinId, usize> = HashMap::new();
+        for &id in basins.iter().flatten().flatten() {
+            *sizes.entry(id).or_default() += 1;
+        }
+        assert_eq!(sizes.len(), 4);
+
+        let (_, result) = Basins::new(map).compute_basins();
+        let mut flow_sizes: Vec<_> = sizes.into_values().collect();
+        flow_sizes.sort_unstable();
+        let mut bfs_sizes: Vec<_> = result.basin_sizes().collect();
+        bfs_sizes.sort_unstable();
+        assert_eq!(flow_sizes, bfs_sizes);
+    }
+
+    #[test]
+    fn merge_basins() {
+        // Flood filled basins never touch
+        let map = Map::from_str(TEST_INPUT).unwrap();
+        let (_, mut result) = Basins::new(map).compute_basins();
+        result.merge_basins(100);
+        assert_eq!(result.basin_sizes().count(), 4);
+
+        let basin = |points: &[(i32, i32)]| points.iter().copied().collect::<HashSet<_>>();
+        let mut result = BasinsResult {
+            basin_sizes: HashMap::from([(0, 1), (1, 3), (2, 2), (3, 1)]),
+            basin_points: HashMap::from([
+                (0, basin(&[(0, 0)])),
+                (1, basin(&[(1, 0), (2, 0), (3, 0)])),
+                (2, basin(&[(0, 1), (0, 2)])),
+                (3, basin(&[(5, 5)])),
+            ]),
+        };
+        // No basin has fewer than one point
+        result.merge_basins(1);
+        assert_eq!(result.basin_sizes().count(), 4);
+
+        result.merge_basins(2);
+        // The single cell at the origin joins the larger of its neighbors, and
+        // the isolated cell is left alone
+        assert_eq!(result.basin_sizes, HashMap::from([(1, 4), (2, 2), (3, 1)]));
+        assert_eq!(result.basin_points(0), None);
+        assert!(result.basin_points(1).unwrap().contains(&(0, 0)));
+        assert_eq!(result.basin_sizes().sum::<usize>(), 7);
+
+        result.merge_basins(3);
+        assert_eq!(result.basin_sizes, HashMap::from([(1, 6), (3, 1)]));
+        assert_eq!(result.basin_points(1).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn render() {
+        let map = Map::from_str(TEST_INPUT).unwrap();
+        let expected = "\
+21...43210
+3.878.4.21
+.85678.8.2
+87678.678.
+.8...65678
+";
+        assert_eq!(map.render(), expected);
+
+        let (map, result) = Basins::new(map).compute_basins();
+        let colored = map.render_with_basins(&result);
+
+        // Stripping the escape codes gives the plain rendering
+        let mut stripped = String::new();
+        let mut chars = colored.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.by_ref().find(|&c| c == 'm');
+            } else {
+                stripped.push(c);
+            }
+        }
+        assert_eq!(stripped, expected);
+
+        assert_eq!(colored.matches("\x1b[0m").count(), 50 - 15);
+        assert_eq!(colored.matches("\x1b[1;").count(), 4);
+    }
+
+    #[test]
+    fn basin_points() {
+        let map = Map::from_str(TEST_INPUT).unwrap();
+        let (_, result) = Basins::new(map).compute_basins();
+
+        let mut sizes: Vec<_> = result
+            .all_basin_points()
+            .map(|(_, points)| points.len())
+            .collect();
+        sizes.sort_unstable();
+        assert_eq!(sizes, [3, 9, 9, 14]);
+
+        for (id, points) in result.all_basin_points() {
+            assert_eq!(result.basin_points(id), Some(points));
+            assert_eq!(result.basin_sizes[&id], points.len());
+        }
+        assert!(result.basin_points(4).is_none());
+
+        // The top left basin
+        let (id, _) = result
+            .all_basin_points()
+            .find(|(_, points)| points.contains(&(0, 0)))
+            .unwrap();
+        let points = result.basin_points(id).unwrap();
+        assert_eq!(points, &HashSet::from([(0, 0), (1, 0), (0, 1)]));
+    }
 }
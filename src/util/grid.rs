@@ -0,0 +1,262 @@
+use std::{ops::Index, slice::SliceIndex};
+
+/// A rectangular grid of values, stored row by row
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    data: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Panics if `data` does not contain exactly `width * height` values
+    pub fn new(width: usize, height: usize, data: Vec<T>) -> Self {
+        assert_eq!(data.len(), width * height);
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Total number of values
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// All values, row by row
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn data_mut(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Option<&T> {
+        let index = self.index(x, y)?;
+        Some(&self.data[index])
+    }
+
+    pub fn get_mut(&mut self, x: i32, y: i32) -> Option<&mut T> {
+        let index = self.index(x, y)?;
+        Some(&mut self.data[index])
+    }
+
+    /// Iterates over all values along with their coordinates, row by row
+    pub fn iter_coords(&self) -> impl Iterator<Item = (i32, i32, &T)> + '_ {
+        let width = self.width;
+        self.data
+            .iter()
+            .enumerate()
+            .map(move |(i, value)| ((i % width) as i32, (i / width) as i32, value))
+    }
+
+    /// Iterates over the (up to four) values directly left, right, above and
+    /// below the given point
+    pub fn neighbors4(&self, x: i32, y: i32) -> impl Iterator<Item = (i32, i32, &T)> + '_ {
+        IntoIterator::into_iter([(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)])
+            .filter_map(move |(x, y)| Some((x, y, self.get(x, y)?)))
+    }
+
+    #[inline]
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+
+        Some(y as usize * self.width + x as usize)
+    }
+}
+
+/// Indexes into the values row by row, as with `data`
+impl<T, I: SliceIndex<[T]>> Index<I> for Grid<T> {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        &self.data[index]
+    }
+}
+
+/// Parses a grid of single digits, one row per line, returning its width,
+/// height and values row by row. Whitespace between digits is ignored, but
+/// every row must have the same number of digits
+pub fn parse_digit_grid(input: &str) -> Option<(usize, usize, Vec<u8>)> {
+    let mut width = None;
+    let mut height = 0;
+    let mut data = Vec::new();
+    for line in input.trim_end().lines() {
+        let row_start = data.len();
+        for c in line.chars().filter(|c| !c.is_whitespace()) {
+            data.push(c.to_digit(10)? as u8);
+        }
+
+        let row_width = data.len() - row_start;
+        if row_width == 0 || row_width != *width.get_or_insert(row_width) {
+            return None;
+        }
+        height += 1;
+    }
+    Some((width?, height, data))
+}
+
+/// Like `parse_digit_grid`, but allows no whitespace within rows and
+/// describes what went wrong
+pub fn parse_digit_grid_strict(input: &str) -> Result<(usize, usize, Vec<u8>), String> {
+    let mut width = None;
+    let mut height = 0;
+    let mut data = Vec::new();
+    for (line_no, line) in input.lines().enumerate() {
+        let line = line.trim_end();
+        let row_width = line.chars().count();
+        let expected = *width.get_or_insert(row_width);
+        if expected == 0 {
+            return Err(format!("line {} is empty (zero width)", line_no + 1));
+        }
+        if row_width != expected {
+            return Err(format!(
+                "line {} has {} characters, expected {}",
+                line_no + 1,
+                row_width,
+                expected
+            ));
+        }
+
+        for (col, c) in line.chars().enumerate() {
+            let digit = c.to_digit(10).ok_or_else(|| {
+                format!(
+                    "non-digit character {:?} at line {}, column {}",
+                    c,
+                    line_no + 1,
+                    col + 1
+                )
+            })?;
+            data.push(digit as u8);
+        }
+        height += 1;
+    }
+
+    match width {
+        Some(width) => Ok((width, height, data)),
+        None => Err("empty input".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Grid<u8> {
+        Grid::new(3, 2, vec![1, 2, 3, 4, 5, 6])
+    }
+
+    #[test]
+    fn test_get() {
+        let mut grid = example();
+        assert_eq!(grid.get(0, 0), Some(&1));
+        assert_eq!(grid.get(2, 0), Some(&3));
+        assert_eq!(grid.get(1, 1), Some(&5));
+        assert_eq!(grid.get(3, 0), None);
+        assert_eq!(grid.get(0, 2), None);
+        assert_eq!(grid.get(-1, 0), None);
+
+        assert_eq!(grid.len(), 6);
+        assert_eq!(grid[4], 5);
+        assert_eq!(&grid[..2], &[1, 2]);
+
+        *grid.get_mut(1, 1).unwrap() = 9;
+        assert_eq!(grid.data(), &[1, 2, 3, 4, 9, 6]);
+        assert!(grid.get_mut(0, -1).is_none());
+    }
+
+    #[test]
+    fn test_iter_coords() {
+        let grid = example();
+        let coords: Vec<_> = grid.iter_coords().collect();
+        assert_eq!(
+            coords,
+            [
+                (0, 0, &1),
+                (1, 0, &2),
+                (2, 0, &3),
+                (0, 1, &4),
+                (1, 1, &5),
+                (2, 1, &6)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_neighbors4() {
+        let grid = example();
+        let neighbors: Vec<_> = grid.neighbors4(0, 0).collect();
+        assert_eq!(neighbors, [(1, 0, &2), (0, 1, &4)]);
+        let neighbors: Vec<_> = grid.neighbors4(1, 1).collect();
+        assert_eq!(neighbors, [(0, 1, &4), (2, 1, &6), (1, 0, &2)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_wrong_size() {
+        Grid::new(2, 2, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_parse_digit_grid() {
+        assert_eq!(
+            parse_digit_grid("123\n456\n"),
+            Some((3, 2, vec![1, 2, 3, 4, 5, 6]))
+        );
+        assert_eq!(parse_digit_grid("12\r\n34"), Some((2, 2, vec![1, 2, 3, 4])));
+        assert_eq!(
+            parse_digit_grid("1 2\n3 4\n\n"),
+            Some((2, 2, vec![1, 2, 3, 4]))
+        );
+
+        assert_eq!(parse_digit_grid(""), None);
+        assert_eq!(parse_digit_grid("\n12\n"), None);
+        assert_eq!(parse_digit_grid("12\n3a\n"), None);
+        assert_eq!(parse_digit_grid("12\n345\n"), None);
+        assert_eq!(parse_digit_grid("12\n3\n456\n"), None);
+        assert_eq!(parse_digit_grid("12\n\n34\n"), None);
+    }
+
+    #[test]
+    fn test_parse_digit_grid_strict() {
+        assert_eq!(
+            parse_digit_grid_strict("123\n456\n"),
+            Ok((3, 2, vec![1, 2, 3, 4, 5, 6]))
+        );
+        assert_eq!(parse_digit_grid_strict("12"), Ok((2, 1, vec![1, 2])));
+
+        assert_eq!(parse_digit_grid_strict(""), Err("empty input".to_string()));
+        assert_eq!(
+            parse_digit_grid_strict("\n12\n"),
+            Err("line 1 is empty (zero width)".to_string())
+        );
+        assert_eq!(
+            parse_digit_grid_strict("12\n3a\n"),
+            Err("non-digit character 'a' at line 2, column 2".to_string())
+        );
+        assert_eq!(
+            parse_digit_grid_strict("12\n3\n456\n"),
+            Err("line 2 has 1 characters, expected 2".to_string())
+        );
+        assert_eq!(
+            parse_digit_grid_strict("1 2\n3 4\n"),
+            Err("non-digit character ' ' at line 1, column 2".to_string())
+        );
+    }
+}